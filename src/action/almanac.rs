@@ -0,0 +1,142 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+use chrono_tz::Tz;
+use thiserror::Error;
+
+use crate::{
+    astro::{
+        moon::{next_full_moon, next_new_moon, Moon},
+        sun::{Crossing, SolarDay},
+    },
+    err::RKBServiceRequestErr,
+    geo,
+    weather::Location,
+    RKBServiceRequest,
+};
+
+const DATE_FORMAT: &str = "%Y-%m-%d";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("user input date does not follow YYYY-MM-DD")]
+    InvalidDate(String),
+}
+
+struct SunReport {
+    geo: Location,
+    tz: Tz,
+    today: SolarDay,
+    yesterday: SolarDay,
+}
+
+impl SunReport {
+    fn crossing(&self, crossing: &Crossing) -> String {
+        match crossing {
+            Crossing::Times { rise, set } => format!(
+                "{} – {}",
+                rise.with_timezone(&self.tz).format("%H:%M"),
+                set.with_timezone(&self.tz).format("%H:%M")
+            ),
+            Crossing::AlwaysAbove => String::from("sun stays up"),
+            Crossing::AlwaysBelow => String::from("sun stays down"),
+        }
+    }
+}
+
+impl Display for SunReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let change = self.today.day_length() - self.yesterday.day_length();
+        let sign = match change < TimeDelta::zero() {
+            true => "-",
+            false => "+",
+        };
+        write!(
+            f,
+            ">>> ■ {} ({}, {})\n🌅 Sun {}\n☀️ Solar noon {}\nDay length {} ({}{} vs yesterday)\nCivil twilight {}\nNautical twilight {}\nAstronomical twilight {}",
+            self.geo.name,
+            self.today.date.format(DATE_FORMAT),
+            self.tz,
+            self.crossing(&self.today.sunrise),
+            self.today.noon.with_timezone(&self.tz).format("%H:%M"),
+            format_delta(self.today.day_length()),
+            sign,
+            format_delta(change.abs()),
+            self.crossing(&self.today.civil),
+            self.crossing(&self.today.nautical),
+            self.crossing(&self.today.astronomical),
+        )
+    }
+}
+
+struct MoonReport {
+    at: DateTime<Utc>,
+    moon: Moon,
+}
+
+impl Display for MoonReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            ">>> ■ {}\n{:.0}% illuminated, {:.1} days old\nNext full moon <t:{}:F>\nNext new moon <t:{}:F>",
+            self.moon.phase(),
+            self.moon.illumination * 100.0,
+            self.moon.age(),
+            next_full_moon(self.at).timestamp(),
+            next_new_moon(self.at).timestamp(),
+        )
+    }
+}
+
+impl RKBServiceRequest {
+    pub async fn sun(self) -> Result<(), RKBServiceRequestErr> {
+        let (location, date) = split_date(self.get_content().unwrap_or_default())?;
+        let geo = self.geocode(location, false).await?;
+        let tz = geo::timezone(geo.lat, geo.lon);
+        let date = date.unwrap_or(Utc::now().with_timezone(&tz).date_naive());
+        let yesterday = date.pred_opt().unwrap_or(date);
+        let report = SunReport {
+            today: SolarDay::new(date, geo.lat, geo.lon),
+            yesterday: SolarDay::new(yesterday, geo.lat, geo.lon),
+            geo,
+            tz,
+        };
+        self.try_send_message(report.to_string()).await?;
+        Ok(())
+    }
+
+    pub async fn moon(self) -> Result<(), RKBServiceRequestErr> {
+        let (_, date) = split_date(self.get_content().unwrap_or_default())?;
+        let at = date
+            .and_then(|v| v.and_hms_opt(12, 0, 0))
+            .map(|v| v.and_utc())
+            .unwrap_or(Utc::now());
+        let report = MoonReport {
+            at,
+            moon: Moon::at(at),
+        };
+        self.try_send_message(report.to_string()).await?;
+        Ok(())
+    }
+}
+
+/// Splits a trailing YYYY-MM-DD date from the rest of the content.
+fn split_date(content: &str) -> Result<(&str, Option<NaiveDate>), RKBServiceRequestErr> {
+    let (rest, last) = content.rsplit_once(' ').unwrap_or(("", content));
+    if !last.starts_with(|c: char| c.is_ascii_digit()) || !last.contains('-') {
+        return Ok((content, None));
+    }
+    let date = NaiveDate::parse_from_str(last, DATE_FORMAT)
+        .map_err(|_| Error::InvalidDate(last.to_string()))?;
+    Ok((rest.trim(), Some(date)))
+}
+
+fn format_delta(delta: TimeDelta) -> String {
+    let hours = delta.num_hours();
+    let minutes = delta.num_minutes() % 60;
+    let seconds = delta.num_seconds() % 60;
+    match hours > 0 {
+        true => format!("{}h {}m", hours, minutes),
+        false => format!("{}m {}s", minutes, seconds),
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Display;
+
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::{
+    err::RKBServiceRequestErr,
+    split_flags,
+    token::TokenType,
+    weather::{openweather::OpenWeather, Location},
+    RKBServiceRequest,
+};
+
+const TREND_HOURS: usize = 24;
+const TREND_STEP_HOURS: usize = 3;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("openweather returned no air pollution data")]
+    AirPollutionEmpty,
+}
+
+/// Air pollution report, current or forecast.
+#[derive(Deserialize, Debug)]
+pub struct AirPollutionJson {
+    /// hourly entries, a single one for current conditions
+    pub list: Vec<AirPollution>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AirPollution {
+    /// Time of data calculation, unix, UTC
+    pub dt: i64,
+
+    /// air quality index
+    pub main: AirQuality,
+
+    /// pollutant concentrations
+    pub components: Components,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AirQuality {
+    /// Air Quality Index. 1 = Good, 2 = Fair, 3 = Moderate, 4 = Poor, 5 = Very Poor.
+    pub aqi: u8,
+}
+
+/// Pollutant concentrations, μg/m³
+#[derive(Deserialize, Debug)]
+pub struct Components {
+    /// Fine particles matter
+    pub pm2_5: f64,
+
+    /// Coarse particulate matter
+    pub pm10: f64,
+
+    /// Ozone
+    pub o3: f64,
+
+    /// Nitrogen dioxide
+    pub no2: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum AqiCategory {
+    Good,
+    Fair,
+    Moderate,
+    Poor,
+    VeryPoor,
+}
+
+impl AqiCategory {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            AqiCategory::Good => "🟢",
+            AqiCategory::Fair => "🟡",
+            AqiCategory::Moderate => "🟠",
+            AqiCategory::Poor => "🔴",
+            AqiCategory::VeryPoor => "🟣",
+        }
+    }
+}
+
+impl From<u8> for AqiCategory {
+    fn from(value: u8) -> Self {
+        match value {
+            0 | 1 => AqiCategory::Good,
+            2 => AqiCategory::Fair,
+            3 => AqiCategory::Moderate,
+            4 => AqiCategory::Poor,
+            _ => AqiCategory::VeryPoor,
+        }
+    }
+}
+
+impl Display for AqiCategory {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            AqiCategory::Good => "Good",
+            AqiCategory::Fair => "Fair",
+            AqiCategory::Moderate => "Moderate",
+            AqiCategory::Poor => "Poor",
+            AqiCategory::VeryPoor => "Very Poor",
+        };
+        write!(f, "{} {}", self.emoji(), name)
+    }
+}
+
+impl AirPollution {
+    pub fn category(&self) -> AqiCategory {
+        AqiCategory::from(self.main.aqi)
+    }
+}
+
+struct AirQualityReport {
+    geo: Location,
+    current: AirPollution,
+    forecast: Option<Vec<AirPollution>>,
+}
+
+impl Display for AirQualityReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let components = &self.current.components;
+        write!(
+            f,
+            ">>> ■ {}\nAQI {} - {}\nPM2.5 {:.1} μg/m³\nPM10 {:.1} μg/m³\nO₃ {:.1} μg/m³\nNO₂ {:.1} μg/m³",
+            self.geo.name,
+            self.current.main.aqi,
+            self.current.category(),
+            components.pm2_5,
+            components.pm10,
+            components.o3,
+            components.no2,
+        )?;
+        let Some(forecast) = &self.forecast else {
+            return Ok(());
+        };
+        let window = &forecast[..forecast.len().min(TREND_HOURS)];
+        let trend = window
+            .iter()
+            .step_by(TREND_STEP_HOURS)
+            .map(|v| v.category().emoji())
+            .collect::<String>();
+        let (Some(first), Some(worst), Some(last)) = (
+            window.first(),
+            window.iter().max_by_key(|v| v.main.aqi),
+            window.last(),
+        ) else {
+            return Ok(());
+        };
+        let direction = match last.main.aqi.cmp(&first.main.aqi) {
+            std::cmp::Ordering::Less => "improving",
+            std::cmp::Ordering::Equal => "steady",
+            std::cmp::Ordering::Greater => "worsening",
+        };
+        write!(
+            f,
+            "\n{}h trend: {} ({}, worst {})",
+            TREND_HOURS,
+            trend,
+            direction,
+            worst.category()
+        )
+    }
+}
+
+impl RKBServiceRequest {
+    pub async fn aqi(self) -> Result<(), RKBServiceRequestErr> {
+        let (location, flags) = split_flags(self.get_content().unwrap_or_default());
+        let geo = self
+            .geocode(&location, flags.iter().any(|v| v == "fresh"))
+            .await?;
+        let api_key = self.tkn.get(&TokenType::OpenWeather)?;
+        let open_weather = OpenWeather::new(&self.tkn, api_key.to_string());
+        let coordinates = [("lat", geo.lat.to_string()), ("lon", geo.lon.to_string())];
+        let current = open_weather
+            .get::<AirPollutionJson>("/data/2.5/air_pollution", &coordinates)
+            .await?
+            .list
+            .into_iter()
+            .next()
+            .ok_or(Error::AirPollutionEmpty)?;
+        let forecast = match flags.iter().any(|v| v == "trend" || v == "forecast") {
+            true => Some(
+                open_weather
+                    .get::<AirPollutionJson>("/data/2.5/air_pollution/forecast", &coordinates)
+                    .await?
+                    .list,
+            ),
+            false => None,
+        };
+        let report = AirQualityReport {
+            geo,
+            current,
+            forecast,
+        };
+        self.try_send_message(report.to_string()).await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,225 @@
+use serenity::all::{Attachment, Message};
+
+use crate::{
+    llm::{estimate_tokens, ChatMessage, Role},
+    RKBServiceRequest,
+};
+
+/// Larger files aren't downloaded.
+const MAX_ATTACHMENT_SIZE: u32 = 512 * 1024;
+/// Estimated tokens of file contents added to the prompt, shared by all files.
+const ATTACHMENT_TOKEN_BUDGET: usize = 4000;
+const TEXT_CONTENT_TYPES: [&str; 7] = [
+    "application/json",
+    "application/xml",
+    "application/toml",
+    "application/yaml",
+    "application/x-yaml",
+    "application/javascript",
+    "application/x-sh",
+];
+const TEXT_EXTENSIONS: [&str; 32] = [
+    "txt", "log", "md", "rs", "toml", "yaml", "yml", "json", "xml", "csv", "ini", "cfg", "conf",
+    "py", "js", "ts", "c", "h", "cpp", "hpp", "go", "java", "kt", "rb", "sh", "sql", "html", "css",
+    "diff", "patch", "lock", "trace",
+];
+
+impl RKBServiceRequest {
+    /// Text files attached to the invoking message and the message it replies to, as a user
+    /// message of fenced blocks, with notes on files cut short or left out.
+    pub(crate) async fn attached_files(&self) -> (Option<ChatMessage>, Vec<String>) {
+        let mut messages = vec![self.msg.clone()];
+        if let Some(referenced) = self.referenced_message().await {
+            messages.push(referenced);
+        }
+        let mut attachments = messages
+            .into_iter()
+            .flat_map(|v| v.attachments)
+            .filter(is_text)
+            .collect::<Vec<Attachment>>();
+        attachments.dedup_by_key(|v| v.id);
+        let mut budget = ATTACHMENT_TOKEN_BUDGET;
+        let mut blocks = Vec::new();
+        let mut notes = Vec::new();
+        for attachment in attachments {
+            if attachment.size > MAX_ATTACHMENT_SIZE {
+                notes.push(format!(
+                    "{} left out, over {} KiB",
+                    attachment.filename,
+                    MAX_ATTACHMENT_SIZE / 1024
+                ));
+                continue;
+            }
+            let Some(text) = attachment
+                .download()
+                .await
+                .ok()
+                .and_then(|v| String::from_utf8(v).ok())
+            else {
+                notes.push(format!(
+                    "{} left out, not readable text",
+                    attachment.filename
+                ));
+                continue;
+            };
+            if budget == 0 {
+                notes.push(format!("{} left out, no room left", attachment.filename));
+                continue;
+            }
+            let kept = within_tokens(&text, budget);
+            budget -= estimate_tokens(kept);
+            if kept.len() < text.len() {
+                notes.push(format!(
+                    "{} cut to {} of {} characters",
+                    attachment.filename,
+                    kept.chars().count(),
+                    text.chars().count()
+                ));
+            }
+            blocks.push(fenced_block(&attachment.filename, kept));
+        }
+        let message = (!blocks.is_empty()).then(|| {
+            ChatMessage::new(
+                Role::User,
+                format!("Attached files:\n\n{}", blocks.join("\n\n")),
+            )
+        });
+        (message, notes)
+    }
+
+    /// The message the invoking message replies to.
+    async fn referenced_message(&self) -> Option<Message> {
+        if let Some(message) = self.msg.referenced_message.as_deref() {
+            return Some(message.clone());
+        }
+        let reference = self.msg.message_reference.as_ref()?;
+        self.fetch_message(reference.channel_id, reference.message_id?)
+            .await
+    }
+}
+
+/// Text by MIME type, or by extension when Discord didn't tell.
+fn is_text(attachment: &Attachment) -> bool {
+    let content_type = attachment
+        .content_type
+        .as_deref()
+        .and_then(|v| v.split(';').next())
+        .unwrap_or_default()
+        .trim();
+    content_type.starts_with("text/")
+        || TEXT_CONTENT_TYPES.contains(&content_type)
+        || TEXT_EXTENSIONS.contains(&extension(&attachment.filename).as_str())
+}
+
+fn extension(filename: &str) -> String {
+    filename
+        .rsplit_once('.')
+        .map(|(_, v)| v.to_lowercase())
+        .unwrap_or_default()
+}
+
+/// The start of `text` that fits in `budget` estimated tokens.
+fn within_tokens(text: &str, budget: usize) -> &str {
+    let mut used = 0;
+    let end = text
+        .char_indices()
+        .find(|(_, c)| {
+            used += if c.is_ascii() { 1 } else { 4 };
+            used > budget * 4
+        })
+        .map_or(text.len(), |(i, _)| i);
+    &text[..end]
+}
+
+/// `text` fenced with one backtick more than the longest run inside it.
+fn fenced_block(filename: &str, text: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!(
+        "**{}**\n{}{}\n{}\n{}",
+        filename,
+        fence,
+        extension(filename),
+        text.trim_end(),
+        fence
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn attachment(filename: &str, content_type: Option<&str>) -> Attachment {
+        serde_json::from_value(json!({
+            "id": "1",
+            "filename": filename,
+            "size": 10,
+            "url": "https://cdn.example/file",
+            "proxy_url": "https://media.example/file",
+            "content_type": content_type,
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn cuts_at_the_budget() {
+        assert_eq!(within_tokens("abcdefgh", 1), "abcd");
+        assert_eq!(within_tokens("abcd", 1), "abcd");
+        assert_eq!(within_tokens("", 1), "");
+        assert_eq!(within_tokens("abc", 0), "");
+    }
+
+    #[test]
+    fn cuts_multibyte_text_on_a_char_boundary() {
+        // Non-ASCII characters count as a token each.
+        assert_eq!(within_tokens("éèê", 2), "éè");
+        assert_eq!(within_tokens("abcd🦀e", 2), "abcd🦀");
+        assert_eq!(within_tokens("abcde🦀", 2), "abcde");
+        assert_eq!(within_tokens("🦀🦀", 2), "🦀🦀");
+    }
+
+    #[test]
+    fn fences_past_backtick_runs() {
+        assert_eq!(
+            fenced_block("a.rs", "fn main() {}\n"),
+            "**a.rs**\n```rs\nfn main() {}\n```"
+        );
+        let text = "before\n````\ninner\n````\nafter";
+        assert_eq!(
+            fenced_block("notes.md", text),
+            format!("**notes.md**\n`````md\n{}\n`````", text)
+        );
+        assert_eq!(fenced_block("LICENSE", "`a`"), "**LICENSE**\n```\n`a`\n```");
+    }
+
+    #[test]
+    fn detects_text_by_content_type() {
+        assert!(is_text(&attachment(
+            "a.bin",
+            Some("text/plain; charset=utf-8")
+        )));
+        assert!(is_text(&attachment(
+            "a.bin",
+            Some("application/json;charset=UTF-8")
+        )));
+        assert!(!is_text(&attachment("a.png", Some("image/png"))));
+        assert!(!is_text(&attachment("a", Some("application/octet-stream"))));
+    }
+
+    #[test]
+    fn detects_text_by_extension() {
+        assert!(is_text(&attachment("Main.RS", None)));
+        assert!(is_text(&attachment("archive.tar.log", None)));
+        assert!(!is_text(&attachment("photo.jpeg", None)));
+        assert!(!is_text(&attachment("Makefile", None)));
+        assert_eq!(extension("Cargo.LOCK"), "lock");
+        assert_eq!(extension("README"), "");
+    }
+}
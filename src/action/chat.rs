@@ -0,0 +1,564 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    AutoArchiveDuration, ChannelId, CreateAllowedMentions, CreateAttachment, CreateThread,
+    EditMessage, ExecuteWebhook, Message, UserId, Webhook,
+};
+use thiserror::Error;
+use tokio::{sync::mpsc, time::Instant};
+use tracing::error;
+
+use crate::{
+    action::{
+        persona::Persona,
+        tools::{chat_tools, tool_label},
+    },
+    breakdown_string,
+    cache::TtlCache,
+    err::RKBServiceRequestErr,
+    llm::{ChatDelta, ChatMessage, ChatResponse, LlmProvider, Role, ToolDefinition},
+    split_flags, RKBServiceRequest, ENTRY_STRING,
+};
+
+const CHAT_THREADS_DOCUMENT: &str = "chat_threads";
+const MAX_CHAT_THREADS: usize = 200;
+const THREAD_NAME_LENGTH: usize = 80;
+/// Discord archives the thread after this long without messages.
+const THREAD_ARCHIVE_DURATION: AutoArchiveDuration = AutoArchiveDuration::OneHour;
+const DEFAULT_PERSONA_PROMPT: &str = "Be short and concise. Cite your sources.";
+const SPEAKER_NOTE: &str = "User messages start with the speaker's name.";
+pub(crate) const SKELETON_MESSAGE: &str = "*..*";
+const STREAM_EDIT_INTERVAL: Duration = Duration::from_millis(1500);
+const STREAM_PREVIEW_LENGTH: usize = 1990;
+const THINKING_MESSAGE: &str = "*thinking..*";
+/// Reasoning that doesn't fit in one message with the answer is attached instead.
+const INLINE_REASONING_LENGTH: usize = 2000;
+const REASONING_FILENAME: &str = "reasoning.md";
+/// Inline reasoning is a spoiler between these, ahead of the answer.
+const REASONING_START: &str = "**Reasoning**\n||";
+const REASONING_END: &str = "||\n\n";
+/// Usage, tools and file notes go under the answer as Discord subtext.
+const FOOTER_PREFIX: &str = "-# ";
+/// Model replies per answer, the ones calling tools included.
+const MAX_TOOL_ROUNDS: usize = 4;
+/// Pin changes forget a channel's pinned prompt sooner.
+const PINNED_PROMPT_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("chat reply stream stopped")]
+    StreamStopped,
+}
+
+/// A thread started by `--thread`, where every message continues the conversation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatThread {
+    pub thread_id: ChannelId,
+    pub author_id: UserId,
+    /// Question the thread was started from, it stays in the parent channel.
+    pub prompt: String,
+    pub reasoning: bool,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChatThreadStore {
+    threads: Vec<ChatThread>,
+}
+
+/// Pinned prompt of each channel, so chatting there doesn't fetch the pins on every message.
+#[derive(Debug)]
+pub struct PinnedPromptCache {
+    prompts: TtlCache<ChannelId, Option<Message>>,
+}
+
+impl Default for PinnedPromptCache {
+    fn default() -> Self {
+        PinnedPromptCache {
+            prompts: TtlCache::new(PINNED_PROMPT_TTL),
+        }
+    }
+}
+
+impl PinnedPromptCache {
+    /// Drops the channel's pinned prompt, to be read again when its pins change.
+    pub fn forget(&self, channel_id: ChannelId) {
+        self.prompts.remove(&channel_id);
+    }
+}
+
+impl RKBServiceRequest {
+    pub async fn chat(
+        self,
+        reasoning: bool,
+        preprompt: Option<String>,
+    ) -> Result<(), RKBServiceRequestErr> {
+        if let Some(reason) = self.quota_exceeded() {
+            self.try_send_message(reason).await?;
+            return Ok(());
+        }
+        let (prompt, flags) = split_flags(self.get_content().unwrap_or_default());
+        if flags.iter().any(|v| v == "thread") {
+            return self.start_chat_thread(reasoning, prompt).await;
+        }
+        let messages = match self.chat_thread() {
+            Some(thread) => self.clone().thread_messages(thread).await,
+            None => self.clone().chat_messages(preprompt).await,
+        };
+        self.stream_reply(reasoning, messages, true).await
+    }
+
+    /// Continues the conversation when a user writes in a chat thread, returns whether it did.
+    pub async fn thread_handle_message(self) -> bool {
+        if self.msg.content.starts_with(ENTRY_STRING) {
+            return false;
+        }
+        let Some(thread) = self.chat_thread() else {
+            return false;
+        };
+        tokio::spawn(async move {
+            if let Some(reason) = self.quota_exceeded() {
+                if let Err(err) = self.try_send_message(reason).await {
+                    error!("Failed to send chat quota notice: {:?}", err);
+                }
+                return;
+            }
+            let reasoning = thread.reasoning;
+            let messages = self.clone().thread_messages(thread).await;
+            if let Err(err) = self.stream_reply(reasoning, messages, true).await {
+                error!("Failed to continue chat thread: {:?}", err);
+            }
+        });
+        true
+    }
+
+    /// The chat thread this message was sent in.
+    fn chat_thread(&self) -> Option<ChatThread> {
+        self.parent_channel()?;
+        self.rsc
+            .storage
+            .load::<ChatThreadStore>(CHAT_THREADS_DOCUMENT)
+            .threads
+            .into_iter()
+            .find(|v| v.thread_id == self.msg.channel_id)
+    }
+
+    /// Opens a thread on the invoking message and answers there.
+    async fn start_chat_thread(
+        self,
+        reasoning: bool,
+        prompt: String,
+    ) -> Result<(), RKBServiceRequestErr> {
+        let name = match prompt.char_indices().nth(THREAD_NAME_LENGTH) {
+            Some((end, _)) => format!("{}…", &prompt[..end]),
+            None if prompt.is_empty() => format!("Chat with {}", self.msg.author.name),
+            None => prompt.clone(),
+        };
+        let builder = CreateThread::new(name).auto_archive_duration(THREAD_ARCHIVE_DURATION);
+        let Ok(thread) = self
+            .msg
+            .channel_id
+            .create_thread_from_message(&self.ctx.http, self.msg.id, builder)
+            .await
+        else {
+            self.try_send_message(String::from(
+                "Couldn't start a thread here, threads need a server channel and permission. 🧵",
+            ))
+            .await?;
+            return Ok(());
+        };
+        let chat_thread = ChatThread {
+            thread_id: thread.id,
+            author_id: self.msg.author.id,
+            prompt,
+            reasoning,
+        };
+        self.rsc
+            .storage
+            .update(CHAT_THREADS_DOCUMENT, |store: &mut ChatThreadStore| {
+                store.threads.push(chat_thread.clone());
+                let excess = store.threads.len().saturating_sub(MAX_CHAT_THREADS);
+                store.threads.drain(..excess);
+            })?;
+        // Replies go to the thread rather than the channel it was started from.
+        let mut request = self.clone();
+        request.msg.channel_id = thread.id;
+        let messages = request.clone().thread_messages(chat_thread).await;
+        request.stream_reply(reasoning, messages, false).await
+    }
+
+    /// Streams the reply to `messages` into a placeholder message, sent as a reply to the
+    /// invoking message when `reply` is set so the answer can be followed up by replying to it.
+    async fn stream_reply(
+        self,
+        reasoning: bool,
+        mut messages: Vec<ChatMessage>,
+        reply: bool,
+    ) -> Result<(), RKBServiceRequestErr> {
+        let (files, notes) = self.attached_files().await;
+        messages.extend(files);
+        if let Some(persona) = self.channel_persona().filter(|v| v.webhook) {
+            match self.persona_webhook().await {
+                Ok(webhook) => {
+                    return self
+                        .webhook_reply(persona, webhook, reasoning, messages, notes)
+                        .await
+                }
+                Err(err) => error!("Failed to get persona webhook: {:?}", err),
+            }
+        }
+        let provider = self.llm_provider(reasoning)?;
+        let (provider_name, model) = (provider.name(), provider.model().to_string());
+        let hide_reasoning = self.guild_settings().hide_reasoning;
+        let mut skeleton_message = match reply {
+            true => self
+                .msg
+                .reply(&self.ctx.http, SKELETON_MESSAGE)
+                .await
+                .map_err(|_| {
+                    RKBServiceRequestErr::DiscordMessageSendFailure(SKELETON_MESSAGE.to_string())
+                })?,
+            false => {
+                self.clone()
+                    .try_send_message(String::from(SKELETON_MESSAGE))
+                    .await?
+            }
+        };
+        let mut tools_used = Vec::new();
+        let mut round = 1;
+        let response = loop {
+            let response = self
+                .stream_round(
+                    provider.clone(),
+                    messages.clone(),
+                    round_tools(reasoning, round),
+                    &mut skeleton_message,
+                    hide_reasoning,
+                )
+                .await?;
+            self.record_usage(provider.as_ref(), &messages, &response);
+            if response.tool_calls.is_empty() {
+                break response;
+            }
+            let labels = response
+                .tool_calls
+                .iter()
+                .map(tool_label)
+                .collect::<Vec<String>>();
+            let builder = EditMessage::new().content(format!("*🔧 {}..*", labels.join(", ")));
+            let _ = skeleton_message.edit(&self.ctx, builder).await;
+            self.run_tool_calls(&mut messages, response, &mut tools_used)
+                .await;
+            round += 1;
+        };
+        let (mut answer, attachment) =
+            compose_answer(response, reasoning, hide_reasoning, provider_name, &model);
+        answer += &tools_footer(&tools_used);
+        answer += &files_footer(&notes);
+        if let Some(reasoning_text) = attachment {
+            let builder = EditMessage::new()
+                .new_attachment(CreateAttachment::bytes(reasoning_text, REASONING_FILENAME));
+            if let Err(err) = skeleton_message.edit(&self.ctx, builder).await {
+                error!("Failed to attach reasoning: {:?}", err);
+            }
+        }
+        self.try_edit_message(&mut skeleton_message, &answer)
+            .await?;
+        Ok(())
+    }
+
+    /// Streams one model reply into `skeleton_message`, at most one edit per interval.
+    async fn stream_round(
+        &self,
+        provider: Arc<dyn LlmProvider>,
+        messages: Vec<ChatMessage>,
+        tools: Vec<ToolDefinition>,
+        skeleton_message: &mut Message,
+        hide_reasoning: bool,
+    ) -> Result<ChatResponse, RKBServiceRequestErr> {
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let stream = tokio::spawn(async move { provider.stream(&messages, &tools, sender).await });
+        let mut partial = String::new();
+        let mut thinking = false;
+        let mut last_edit = Instant::now();
+        while let Some(delta) = receiver.recv().await {
+            match delta {
+                ChatDelta::Content(content) => partial += &content,
+                ChatDelta::Reasoning(_) if !thinking && !hide_reasoning => {
+                    thinking = true;
+                    let builder = EditMessage::new().content(THINKING_MESSAGE);
+                    let _ = skeleton_message.edit(&self.ctx, builder).await;
+                }
+                ChatDelta::Reasoning(_) => (),
+            }
+            if partial.is_empty() || last_edit.elapsed() < STREAM_EDIT_INTERVAL {
+                continue;
+            }
+            let builder = EditMessage::new().content(stream_preview(&partial));
+            let _ = skeleton_message.edit(&self.ctx, builder).await;
+            last_edit = Instant::now();
+        }
+        stream.await.map_err(|_| Error::StreamStopped)?
+    }
+
+    /// Runs the tools the model asked for and adds the request and results to `messages`.
+    async fn run_tool_calls(
+        &self,
+        messages: &mut Vec<ChatMessage>,
+        response: ChatResponse,
+        tools_used: &mut Vec<String>,
+    ) {
+        messages.push(ChatMessage::tool_request(
+            response.content,
+            response.tool_calls.clone(),
+        ));
+        for call in response.tool_calls {
+            let output = self.run_tool(&call).await;
+            tools_used.push(tool_label(&call));
+            messages.push(ChatMessage::tool_result(call, output));
+        }
+    }
+
+    /// Answers through the persona's webhook, in one go since webhook messages aren't streamed.
+    async fn webhook_reply(
+        self,
+        persona: Persona,
+        webhook: Webhook,
+        reasoning: bool,
+        messages: Vec<ChatMessage>,
+        notes: Vec<String>,
+    ) -> Result<(), RKBServiceRequestErr> {
+        let provider = self.llm_provider(reasoning)?;
+        let typing = self.msg.channel_id.start_typing(&self.ctx.http);
+        let mut messages = messages;
+        let mut tools_used = Vec::new();
+        let mut round = 1;
+        let response = loop {
+            let response = provider
+                .chat(&messages, &round_tools(reasoning, round))
+                .await?;
+            self.record_usage(provider.as_ref(), &messages, &response);
+            if response.tool_calls.is_empty() {
+                break response;
+            }
+            self.run_tool_calls(&mut messages, response, &mut tools_used)
+                .await;
+            round += 1;
+        };
+        typing.stop();
+        let hide_reasoning = self.guild_settings().hide_reasoning;
+        let (mut answer, mut attachment) = compose_answer(
+            response,
+            reasoning,
+            hide_reasoning,
+            provider.name(),
+            provider.model(),
+        );
+        answer += &tools_footer(&tools_used);
+        answer += &files_footer(&notes);
+        for chunk in breakdown_string(answer) {
+            let mut builder = ExecuteWebhook::new()
+                .content(chunk.clone())
+                .username(persona.name.clone())
+                .allowed_mentions(CreateAllowedMentions::new());
+            if let Some(avatar_url) = &persona.avatar_url {
+                builder = builder.avatar_url(avatar_url.clone());
+            }
+            if self.parent_channel().is_some() {
+                builder = builder.in_thread(self.msg.channel_id);
+            }
+            if let Some(reasoning_text) = attachment.take() {
+                builder =
+                    builder.add_file(CreateAttachment::bytes(reasoning_text, REASONING_FILENAME));
+            }
+            webhook
+                .execute(&self.ctx.http, false, builder)
+                .await
+                .map_err(|_| RKBServiceRequestErr::DiscordMessageSendFailure(chunk))?;
+        }
+        Ok(())
+    }
+
+    /// The newest pinned command of the channel written by an admin, or by anyone in direct
+    /// messages. Pins that can't be read count as none.
+    pub(crate) async fn pinned_prompt(&self) -> Option<Message> {
+        let channel_id = self.msg.channel_id;
+        if let Some(pinned) = self.rsc.pinned_prompts.prompts.get(&channel_id) {
+            return pinned;
+        }
+        let pins = match channel_id.pins(&self.ctx.http).await {
+            Ok(pins) => pins,
+            Err(err) => {
+                error!("Failed to read pinned messages: {:?}", err);
+                Vec::new()
+            }
+        };
+        let mut pinned = None;
+        for message in pins {
+            if !message.content.starts_with(ENTRY_STRING) {
+                continue;
+            }
+            if self.msg.guild_id.is_none() || self.is_admin_user(message.author.id).await {
+                pinned = Some(message);
+                break;
+            }
+        }
+        self.rsc
+            .pinned_prompts
+            .prompts
+            .insert(channel_id, pinned.clone());
+        pinned
+    }
+
+    /// The channel's persona prompt, or the default one.
+    fn system_prompt(&self) -> ChatMessage {
+        let prompt = self
+            .channel_persona()
+            .map_or(DEFAULT_PERSONA_PROMPT.to_string(), |v| v.prompt);
+        ChatMessage::system(format!("{} {}", prompt, SPEAKER_NOTE))
+    }
+
+    /// The reply chain when the message replies to another, else the channel's history.
+    async fn chat_messages(self, preprompt: Option<String>) -> Vec<ChatMessage> {
+        let mut messages = match self.msg.message_reference {
+            Some(_) => self.reply_chain_history().await,
+            None => self.chat_history(self.msg.channel_id).await,
+        };
+        messages.insert(0, self.system_prompt());
+        // A persona set for the channel replaces the pinned prompt.
+        if let Some(preprompt) = preprompt.filter(|_| self.channel_persona().is_none()) {
+            messages.insert(0, ChatMessage::system(preprompt));
+        }
+        messages
+    }
+
+    /// The thread's history after the question it was started from.
+    async fn thread_messages(self, thread: ChatThread) -> Vec<ChatMessage> {
+        let mut messages = self.chat_history(thread.thread_id).await;
+        messages.insert(0, ChatMessage::new(Role::User, thread.prompt));
+        messages.insert(0, self.system_prompt());
+        messages
+    }
+}
+
+/// The text to post and the reasoning to attach. Reasoning answers carry the chain of thought,
+/// inline in a spoiler when it fits, and a token usage footer.
+fn compose_answer(
+    response: ChatResponse,
+    reasoning: bool,
+    hide_reasoning: bool,
+    provider_name: &str,
+    model: &str,
+) -> (String, Option<String>) {
+    if !reasoning {
+        return (response.content, None);
+    }
+    let footer = match response.usage {
+        Some(usage) => format!(
+            "\n{}🧮 {} in · {} out · {} `{}`",
+            FOOTER_PREFIX, usage.prompt_tokens, usage.completion_tokens, provider_name, model
+        ),
+        None => format!("\n{}{} `{}`", FOOTER_PREFIX, provider_name, model),
+    };
+    let reasoning_text = response.reasoning.filter(|_| !hide_reasoning);
+    let spoiler = reasoning_text.as_deref().map(|v| {
+        format!(
+            "{}{}{}",
+            REASONING_START,
+            v.trim().replace("||", "|\u{200b}|"),
+            REASONING_END
+        )
+    });
+    let inline = spoiler
+        .filter(|v| v.len() + response.content.len() + footer.len() <= INLINE_REASONING_LENGTH);
+    let attachment = reasoning_text.filter(|_| inline.is_none());
+    let answer = format!(
+        "{}{}{}",
+        inline.unwrap_or_default(),
+        response.content,
+        footer
+    );
+    (answer, attachment)
+}
+
+/// A posted answer as the model wrote it, without the reasoning spoiler and footers.
+pub(crate) fn strip_answer(answer: &str) -> String {
+    let answer = answer
+        .strip_prefix(REASONING_START)
+        .and_then(|v| v.split_once(REASONING_END))
+        .map_or(answer, |(_, v)| v);
+    answer
+        .lines()
+        .filter(|v| !v.starts_with(FOOTER_PREFIX))
+        .collect::<Vec<&str>>()
+        .join("\n")
+}
+
+/// Tools offered in a round of the tool-call loop. Reasoning models get none, and the last
+/// round gets none so the model has to answer.
+fn round_tools(reasoning: bool, round: usize) -> Vec<ToolDefinition> {
+    match reasoning || round >= MAX_TOOL_ROUNDS {
+        true => Vec::new(),
+        false => chat_tools(),
+    }
+}
+
+fn tools_footer(tools_used: &[String]) -> String {
+    match tools_used.is_empty() {
+        true => String::new(),
+        false => format!("\n{}🔧 {}", FOOTER_PREFIX, tools_used.join(" · ")),
+    }
+}
+
+/// Attached files cut short or left out of the prompt.
+fn files_footer(notes: &[String]) -> String {
+    match notes.is_empty() {
+        true => String::new(),
+        false => format!("\n{}📎 {}", FOOTER_PREFIX, notes.join(" · ")),
+    }
+}
+
+/// The start of a partial reply that fits in one message.
+fn stream_preview(partial: &str) -> String {
+    match partial.char_indices().nth(STREAM_PREVIEW_LENGTH) {
+        Some((end, _)) => format!("{}…", &partial[..end]),
+        None => partial.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::llm::Usage;
+
+    #[test]
+    fn strips_what_compose_answer_adds() {
+        let response = ChatResponse {
+            content: String::from("It's **42**.\n\nSee below."),
+            reasoning: Some(String::from("Deep || thought.")),
+            usage: Some(Usage {
+                prompt_tokens: 10,
+                completion_tokens: 20,
+            }),
+            tool_calls: Vec::new(),
+        };
+        let (mut answer, attachment) = compose_answer(response, true, false, "openai", "o3");
+        assert!(answer.starts_with(REASONING_START));
+        assert_eq!(attachment, None);
+        answer += &tools_footer(&[String::from("weather")]);
+        answer += &files_footer(&[String::from("a.txt left out")]);
+        assert_eq!(strip_answer(&answer), "It's **42**.\n\nSee below.");
+    }
+
+    #[test]
+    fn plain_answers_are_kept() {
+        assert_eq!(
+            strip_answer("Just **this**.\n- # list"),
+            "Just **this**.\n- # list"
+        );
+        assert_eq!(
+            strip_answer("**Reasoning** is overrated.\n-# openai `o3`"),
+            "**Reasoning** is overrated."
+        );
+    }
+}
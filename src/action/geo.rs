@@ -0,0 +1,83 @@
+use chrono::Utc;
+use thiserror::Error;
+
+use crate::{
+    err::RKBServiceRequestErr,
+    geo::{self, compass_point, haversine_km, initial_bearing, km_to_miles, parse_coordinates},
+    split_action, split_flags,
+    weather::Location,
+    RKBServiceRequest,
+};
+
+const DISTANCE_SEPARATOR: &str = " to ";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("distance needs two places, like `distance Paris to Berlin`")]
+    MissingPlaces,
+}
+
+impl RKBServiceRequest {
+    pub async fn geo(self) -> Result<(), RKBServiceRequestErr> {
+        let (content, flags) = split_flags(self.get_content().unwrap_or_default());
+        let fresh = flags.iter().any(|v| v == "fresh");
+        let (subaction, place) = split_action(content.clone());
+        let response = match subaction.as_str() {
+            "distance" | "dist" => match self.geo_distance(&place, fresh).await {
+                Err(RKBServiceRequestErr::Geo(err)) => format!("{}. 🧭", err),
+                response => response?,
+            },
+            "tz" | "time" => self.geo_timezone(&place, fresh).await?,
+            _ => self.place(&content, fresh).await?.to_string(),
+        };
+        self.try_send_message(response).await?;
+        Ok(())
+    }
+
+    async fn geo_distance(
+        &self,
+        places: &str,
+        fresh: bool,
+    ) -> Result<String, RKBServiceRequestErr> {
+        // Place names have spaces of their own, so the separator is required.
+        let (from, to) = places
+            .split_once(DISTANCE_SEPARATOR)
+            .map(|(from, to)| (from.trim(), to.trim()))
+            .filter(|(from, to)| !from.is_empty() && !to.is_empty())
+            .ok_or(Error::MissingPlaces)?;
+        let (from, to) = tokio::try_join!(self.place(from, fresh), self.place(to, fresh))?;
+        let (from_coordinates, to_coordinates) = ((from.lat, from.lon), (to.lat, to.lon));
+        let km = haversine_km(from_coordinates, to_coordinates);
+        let bearing = initial_bearing(from_coordinates, to_coordinates);
+        Ok(format!(
+            ">>> ■ {} → {}\n{:.1} km ({:.1} mi)\nBearing {:.0}° {}",
+            from.name,
+            to.name,
+            km,
+            km_to_miles(km),
+            bearing,
+            compass_point(bearing)
+        ))
+    }
+
+    async fn geo_timezone(&self, place: &str, fresh: bool) -> Result<String, RKBServiceRequestErr> {
+        let location = self.place(place, fresh).await?;
+        let tz = geo::timezone(location.lat, location.lon);
+        let now = Utc::now().with_timezone(&tz);
+        Ok(format!(
+            ">>> ■ {}\n{}\n{} (UTC{})",
+            location.name,
+            tz,
+            now.format("%a %Y-%m-%d %H:%M"),
+            now.format("%:z")
+        ))
+    }
+
+    /// Resolves `lat,lon` through reverse geocoding and anything else through geocoding.
+    async fn place(&self, place: &str, fresh: bool) -> Result<Location, RKBServiceRequestErr> {
+        match parse_coordinates(place) {
+            Some((lat, lon)) => self.weather_provider(fresh).reverse_geocode(lat, lon).await,
+            None => self.geocode(place, fresh).await,
+        }
+    }
+}
@@ -1,18 +1,193 @@
 use crate::{err::RKBServiceRequestErr, RKBServiceRequest, ENTRY_STRING};
 
+/// Actions with a summary and their options, as listed in the index.
+const ACTIONS: [(&str, &str, &[&str]); 17] = [
+    (
+        "aqi",
+        "Air quality for [CONTEXT] location.",
+        &["--trend - 24h forecast."],
+    ),
+    ("cache", "Weather cache entries, hits and misses.", &[]),
+    (
+        "chat",
+        "Ask the chat model what you put in [CONTEXT].",
+        &[
+            "--thread - Continue the conversation in a new thread.",
+            "Can check weather, forecasts and places, and set timers.",
+            "Text files attached here or to the replied message are read too.",
+        ],
+    ),
+    (
+        "forecast",
+        "5 day forecast for [CONTEXT] location.",
+        &["--fresh - Skip the cache."],
+    ),
+    (
+        "geo",
+        "Look up [CONTEXT] place or lat,lon coordinates.",
+        &[
+            "distance [A] to [B] - Great-circle distance and bearing.",
+            "tz [PLACE]          - Timezone and local time.",
+        ],
+    ),
+    (
+        "llm",
+        "Chat provider and models in use here.",
+        &[
+            "use [PROVIDER] [MODEL] [REASONING]     - Set for this server. (deepseek, openai, ollama)",
+            "channel [PROVIDER] [MODEL] [REASONING] - Set for this channel.",
+            "reset [channel]                        - Back to the server or bot default.",
+            "reasoning show|hide                    - Chain of thought in REASON answers.",
+        ],
+    ),
+    ("moon", "Moon phase for [CONTEXT] date. (YYYY-MM-DD)", &[]),
+    (
+        "persona",
+        "Persona chat speaks as in this channel.",
+        &[
+            "create|edit [NAME] [PROMPT]     - Save a system prompt. (admin)",
+            "use [NAME|none]                 - Speak as it in this channel. (admin)",
+            "webhook [NAME] on|off [AVATAR]  - Reply under its own name and avatar. (admin)",
+            "list|show [NAME]                - Personas and their prompts.",
+        ],
+    ),
+    (
+        "pomodoro",
+        "Alternate [WORK] [BREAK] [CYCLES].",
+        &[
+            "Default 25 5 4, numbers are minutes.",
+            "cancel [ID] - Stop a pomodoro.",
+        ],
+    ),
+    (
+        "reason",
+        "Ask the reasoning model what you put in [CONTEXT].",
+        &["Answers with its reasoning and token usage."],
+    ),
+    (
+        "remind",
+        "Remind at [CONTEXT] time.",
+        &[
+            "Like tomorrow 9am, next friday 17:00 or in 2 hours. (--dm)",
+            "every [DAYS] [TIME] [MESSAGE]  - Repeat on day, weekday, weekend or mon,wed,...",
+            "cron \"[EXPRESSION]\" [MESSAGE]  - Repeat on a 5 field cron schedule.",
+            "list                           - Recurring reminders in this channel.",
+            "pause|resume|delete [ID]       - Manage a recurring reminder.",
+            "subscribe|unsubscribe [ID]     - Get mentioned by a recurring reminder.",
+            "tz [IANA NAME]                 - Set the timezone reminders are read in.",
+        ],
+    ),
+    (
+        "stopwatch",
+        "Elapsed time of your running stopwatch.",
+        &[
+            "start [LABEL] - Start a stopwatch.",
+            "lap [ID]      - Record a lap.",
+            "stop [ID]     - Stop with a summary of laps.",
+        ],
+    ),
+    (
+        "sun",
+        "Sunrise, sunset and twilight for [CONTEXT] location and date.",
+        &[],
+    ),
+    (
+        "timer",
+        "Set a timer to trigger after time elapsed.",
+        &[
+            "Like 1h30m, 1.5h, 2 weeks or PT1H30M. (--dm)",
+            "cancel [ID]    - Cancel a pending timer.",
+            "max [DURATION] - Longest timer allowed in this server, or default.",
+        ],
+    ),
+    (
+        "timers",
+        "Your pending timers, pomodoros and stopwatches.",
+        &["channel - Everyone's in this channel."],
+    ),
+    (
+        "usage",
+        "Chat tokens and estimated cost.",
+        &[
+            "Of you, this channel and the server.",
+            "quota user|server [TOKENS|off] - Daily token quota. (admin)",
+        ],
+    ),
+    (
+        "weather",
+        "Current weather for [CONTEXT] location.",
+        &[
+            "--detail - Dew point, heat index, wind chill and humidex.",
+            "--fresh  - Skip the cache.",
+            "Compare locations with [A] vs [B] vs [C].",
+        ],
+    ),
+];
+
 impl RKBServiceRequest {
+    /// An index of actions, or the options of the action given as context. Each is its own
+    /// code block under Discord's message length.
     pub async fn help(self) -> Result<(), RKBServiceRequestErr> {
-        let help_text = format!(
-            "```USAGE: 
-{}[ACTION] [CONTEXT]
-
-ACTION:
-CHAT   - Ask DeepSeek AI what you put in [CONTEXT].
-REASON - Ask DeepSeek AI what you put in [CONTEXT].
-TIMER  - Set a timer to trigger after time elapsed. (#d#h#m)```",
-            ENTRY_STRING
-        );
+        let action = self
+            .get_content()
+            .unwrap_or_default()
+            .trim()
+            .trim_start_matches(ENTRY_STRING)
+            .to_lowercase();
+        let help_text = match ACTIONS.iter().find(|(name, _, _)| *name == action) {
+            Some(entry) => action_help(entry),
+            None => index(),
+        };
         self.try_send_message(help_text).await?;
         Ok(())
     }
 }
+
+fn index() -> String {
+    let actions = ACTIONS
+        .iter()
+        .map(|(name, summary, _)| format!("{:<9} - {}", name.to_uppercase(), summary))
+        .collect::<Vec<String>>()
+        .join("\n");
+    format!(
+        "```USAGE:
+{entry}[ACTION] [CONTEXT]
+
+ACTION:
+{actions}
+
+{entry}help [ACTION] lists its options.```",
+        entry = ENTRY_STRING
+    )
+}
+
+fn action_help((name, summary, options): &(&str, &str, &[&str])) -> String {
+    let options = options
+        .iter()
+        .map(|v| format!("\n{:12}{}", "", v))
+        .collect::<String>();
+    format!(
+        "```{}{} [CONTEXT]\n{:<9} - {}{}```",
+        ENTRY_STRING,
+        name,
+        name.to_uppercase(),
+        summary,
+        options
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Discord rejects longer messages, and a code block split across two renders broken.
+    const MAX_HELP_LENGTH: usize = 2000;
+
+    #[test]
+    fn every_message_fits() {
+        assert!(index().len() <= MAX_HELP_LENGTH, "{}", index().len());
+        for entry in ACTIONS.iter() {
+            assert!(action_help(entry).len() <= MAX_HELP_LENGTH, "{}", entry.0);
+        }
+    }
+}
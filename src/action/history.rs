@@ -0,0 +1,466 @@
+use std::{collections::HashMap, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Message, MessageId, MessageReferenceKind};
+use tracing::error;
+
+use crate::{
+    action::chat::{strip_answer, SKELETON_MESSAGE},
+    cache::TtlCache,
+    err::RKBServiceRequestErr,
+    llm::{ChatMessage, Role},
+    split_action, split_flags, RKBServiceRequest, ENTRY_STRING,
+};
+
+/// Messages fetched per request, the most Discord returns at once.
+const HISTORY_SIZE: u8 = 100;
+/// Estimated tokens of history kept verbatim.
+const CONTEXT_TOKEN_BUDGET: usize = 3000;
+/// Older messages are folded into the summary once they add up to this many tokens.
+const SUMMARY_REFRESH_TOKENS: usize = 1000;
+/// Most messages followed up a reply chain, the invoking one included.
+const MAX_REPLY_CHAIN: usize = 24;
+const MESSAGE_TTL: Duration = Duration::from_secs(30 * 60);
+/// Commands whose messages are part of the conversation.
+const CHAT_ACTIONS: [&str; 2] = ["chat", "reason"];
+const CHAT_SUMMARIES_DOCUMENT: &str = "chat_summaries";
+const SUMMARY_PROMPT: &str = "Summarize this Discord conversation for your own later reference. \
+Keep names, facts, decisions and open questions. Use at most 200 words.";
+
+/// Rolling summary of a channel or thread, covering messages up to `through`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatSummary {
+    pub summary: String,
+    pub through: MessageId,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct ChatSummaryStore {
+    channels: HashMap<String, ChatSummary>,
+}
+
+/// Messages by id, so following the same reply chain again doesn't refetch it.
+#[derive(Debug)]
+pub struct MessageCache {
+    messages: TtlCache<MessageId, Message>,
+}
+
+impl Default for MessageCache {
+    fn default() -> Self {
+        MessageCache {
+            messages: TtlCache::new(MESSAGE_TTL),
+        }
+    }
+}
+
+struct HistoryEntry {
+    id: MessageId,
+    message: ChatMessage,
+}
+
+/// How history, newest first, splits around the token budget and the cached summary.
+#[derive(Debug, PartialEq)]
+struct HistorySelection {
+    /// Newest messages within the budget.
+    recent: usize,
+    /// Messages after those that the summary doesn't cover yet, kept verbatim until it does.
+    pending: usize,
+    /// Newest pending message, when the pending ones add up to a summary refresh.
+    refresh_through: Option<MessageId>,
+}
+
+impl RKBServiceRequest {
+    /// Newest messages of the channel within the token budget, oldest first, after a summary of
+    /// the earlier conversation.
+    pub(crate) async fn chat_history(&self, channel_id: ChannelId) -> Vec<ChatMessage> {
+        let messages = self
+            .clone()
+            .read_latest_messages(channel_id, HISTORY_SIZE)
+            .await;
+        let mut history = self.history_entries(messages);
+        let cached = self
+            .rsc
+            .storage
+            .load::<ChatSummaryStore>(CHAT_SUMMARIES_DOCUMENT)
+            .channels
+            .remove(&channel_id.to_string());
+        let selection = select_history(&sizes(&history), cached.as_ref().map(|v| v.through));
+        history.truncate(selection.recent + selection.pending);
+        let pending = history.split_off(selection.recent);
+        let (summary, unsummarized) = self
+            .rolling_summary(
+                channel_id,
+                cached.map(|v| v.summary),
+                pending,
+                selection.refresh_through,
+            )
+            .await;
+        history.extend(unsummarized);
+        let mut messages = history
+            .into_iter()
+            .rev()
+            .map(|v| v.message)
+            .collect::<Vec<ChatMessage>>();
+        if let Some(summary) = summary {
+            messages.insert(
+                0,
+                ChatMessage::system(format!("Summary of the earlier conversation:\n{}", summary)),
+            );
+        }
+        messages
+    }
+
+    /// The reply chain ending at the invoking message, oldest first, within the token budget.
+    /// Replying to an older answer forks the conversation from there.
+    pub(crate) async fn reply_chain_history(&self) -> Vec<ChatMessage> {
+        let mut chain = vec![self.msg.clone()];
+        let mut referenced = self.msg.referenced_message.as_deref().cloned();
+        while chain.len() < MAX_REPLY_CHAIN {
+            let Some(reference) = chain
+                .last()
+                .and_then(|v| v.message_reference.clone())
+                .filter(|v| v.kind == MessageReferenceKind::Default)
+            else {
+                break;
+            };
+            let Some(message_id) = reference.message_id else {
+                break;
+            };
+            let message = match referenced.take().filter(|v| v.id == message_id) {
+                Some(message) => message,
+                None => match self.fetch_message(reference.channel_id, message_id).await {
+                    Some(message) => message,
+                    None => break,
+                },
+            };
+            self.rsc
+                .message_cache
+                .messages
+                .insert(message.id, message.clone());
+            chain.push(message);
+        }
+        let mut history = self.history_entries(chain);
+        history.truncate(within_budget(&sizes(&history)));
+        history.into_iter().rev().map(|v| v.message).collect()
+    }
+
+    pub(crate) async fn fetch_message(
+        &self,
+        channel_id: ChannelId,
+        message_id: MessageId,
+    ) -> Option<Message> {
+        if let Some(message) = self.rsc.message_cache.messages.get(&message_id) {
+            return Some(message);
+        }
+        channel_id.message(&self.ctx.http, message_id).await.ok()
+    }
+
+    /// Conversation turns out of `messages`, newest first. User turns are prefixed with display
+    /// names, other bots, non-chat commands and their replies, and placeholders are dropped, and
+    /// replies the bot split over several messages are merged back.
+    fn history_entries(&self, messages: Vec<Message>) -> Vec<HistoryEntry> {
+        let bot_userid = self.ctx.cache.current_user().id;
+        let persona_webhooks = self.guild_settings().persona_webhooks;
+        let mut entries: Vec<HistoryEntry> = Vec::new();
+        let mut after_command = false;
+        for message in messages.into_iter().rev() {
+            // Persona replies come from the bot's webhooks.
+            let from_bot = message.author.id == bot_userid
+                || message
+                    .webhook_id
+                    .is_some_and(|id| persona_webhooks.values().any(|v| *v == id));
+            if (message.author.bot && !from_bot) || message.author.system {
+                continue;
+            }
+            if !from_bot {
+                let (action, _) = split_action(message.content.clone());
+                after_command = message.content.starts_with(ENTRY_STRING)
+                    && !CHAT_ACTIONS.contains(&action.as_str());
+                if after_command {
+                    continue;
+                }
+            }
+            if from_bot && (after_command || message.content == SKELETON_MESSAGE) {
+                continue;
+            }
+            let id = message.id;
+            let speaker = self.display_name(&message);
+            let mut chat_message = message.to_chat_message(from_bot);
+            if chat_message.content.is_empty() {
+                continue;
+            }
+            if let Some(last) = entries
+                .last_mut()
+                .filter(|v| from_bot && v.message.role == Role::Assistant)
+            {
+                last.message.content += &chat_message.content;
+                last.id = id;
+                continue;
+            }
+            if !from_bot {
+                chat_message.content = format!("{}: {}", speaker, chat_message.content);
+            }
+            entries.push(HistoryEntry {
+                id,
+                message: chat_message,
+            });
+        }
+        entries.reverse();
+        entries
+    }
+
+    /// Server nickname of the author when known, else their display name.
+    fn display_name(&self, message: &Message) -> String {
+        message
+            .member
+            .as_ref()
+            .and_then(|v| v.nick.clone())
+            .or_else(|| {
+                let guild_id = message.guild_id.or(self.msg.guild_id)?;
+                let guild = self.ctx.cache.guild(guild_id)?;
+                guild.members.get(&message.author.id)?.nick.clone()
+            })
+            .unwrap_or_else(|| message.author.display_name().to_string())
+    }
+
+    /// The channel's summary, with `pending` folded into it when a refresh is due. Messages
+    /// not summarized are returned to be kept verbatim.
+    async fn rolling_summary(
+        &self,
+        channel_id: ChannelId,
+        summary: Option<String>,
+        pending: Vec<HistoryEntry>,
+        refresh_through: Option<MessageId>,
+    ) -> (Option<String>, Vec<HistoryEntry>) {
+        let Some(through) = refresh_through else {
+            return (summary, pending);
+        };
+        let refreshed = match self.summarize(summary.as_deref(), &pending).await {
+            Ok(refreshed) => refreshed,
+            Err(err) => {
+                error!("Failed to summarize chat history: {:?}", err);
+                return (summary, pending);
+            }
+        };
+        let updated =
+            self.rsc
+                .storage
+                .update(CHAT_SUMMARIES_DOCUMENT, |store: &mut ChatSummaryStore| {
+                    store.channels.insert(
+                        channel_id.to_string(),
+                        ChatSummary {
+                            summary: refreshed.clone(),
+                            through,
+                        },
+                    )
+                });
+        if let Err(err) = updated {
+            error!("Failed to store chat summary: {:?}", err);
+        }
+        (Some(refreshed), Vec::new())
+    }
+
+    /// Folds `pending`, newest first, into the previous summary.
+    async fn summarize(
+        &self,
+        previous: Option<&str>,
+        pending: &[HistoryEntry],
+    ) -> Result<String, RKBServiceRequestErr> {
+        let transcript = pending
+            .iter()
+            .rev()
+            .map(|v| match v.message.role {
+                Role::Assistant => format!("You: {}", v.message.content),
+                _ => v.message.content.clone(),
+            })
+            .collect::<Vec<String>>()
+            .join("\n");
+        let content = match previous {
+            Some(previous) => format!(
+                "Summary so far:\n{}\n\nNewer messages:\n{}",
+                previous, transcript
+            ),
+            None => transcript,
+        };
+        let provider = self.llm_provider(false)?;
+        let messages = [
+            ChatMessage::system(SUMMARY_PROMPT.to_string()),
+            ChatMessage::new(Role::User, content),
+        ];
+        let response = provider.chat(&messages, &[]).await?;
+        self.record_usage(provider.as_ref(), &messages, &response);
+        Ok(response.content)
+    }
+}
+
+fn sizes(entries: &[HistoryEntry]) -> Vec<(MessageId, usize)> {
+    entries
+        .iter()
+        .map(|v| (v.id, v.message.estimate_tokens()))
+        .collect()
+}
+
+/// How many of the newest `entries`, ids and estimated tokens, fit the token budget, at least one.
+fn within_budget(entries: &[(MessageId, usize)]) -> usize {
+    let mut used = 0;
+    entries
+        .iter()
+        .take_while(|(_, tokens)| {
+            used += tokens;
+            used <= CONTEXT_TOKEN_BUDGET
+        })
+        .count()
+        .clamp(1.min(entries.len()), entries.len())
+}
+
+/// Splits `entries`, ids and estimated tokens newest first, into the recent ones within the
+/// budget and the older ones newer than the summary, which are refreshed into it once they add
+/// up to `SUMMARY_REFRESH_TOKENS`.
+fn select_history(
+    entries: &[(MessageId, usize)],
+    summarized_through: Option<MessageId>,
+) -> HistorySelection {
+    let recent = within_budget(entries);
+    let pending = entries[recent..]
+        .iter()
+        .take_while(|(id, _)| summarized_through.is_none_or(|through| *id > through))
+        .collect::<Vec<&(MessageId, usize)>>();
+    let pending_tokens = pending.iter().map(|(_, tokens)| tokens).sum::<usize>();
+    HistorySelection {
+        recent,
+        pending: pending.len(),
+        refresh_through: pending
+            .first()
+            .map(|(id, _)| *id)
+            .filter(|_| pending_tokens >= SUMMARY_REFRESH_TOKENS),
+    }
+}
+
+trait ToChatMessage {
+    fn to_chat_message(self, from_bot: bool) -> ChatMessage;
+}
+
+impl ToChatMessage for Message {
+    /// The text of the message without its command and flags, or without the reasoning and
+    /// footers of the bot's answers, with embeds and attachments described after it.
+    fn to_chat_message(self, from_bot: bool) -> ChatMessage {
+        let (role, mut content) = match from_bot {
+            true => (Role::Assistant, strip_answer(&self.content)),
+            false if self.content.starts_with(ENTRY_STRING) => {
+                (Role::User, split_flags(&split_action(self.content).1).0)
+            }
+            false => (Role::User, self.content),
+        };
+        for embed in self.embeds {
+            let text = [embed.title, embed.description]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<String>>()
+                .join(" — ");
+            if !text.is_empty() {
+                content += &format!("\n[embed: {}]", text);
+            }
+        }
+        for attachment in self.attachments {
+            content += &format!(
+                "\n[attachment: {} ({}, {} bytes)]",
+                attachment.filename,
+                attachment.content_type.as_deref().unwrap_or("file"),
+                attachment.size
+            );
+        }
+        ChatMessage::new(role, content.trim().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Ids counting down from `first`, newest first like the history.
+    fn entries(first: u64, tokens: &[usize]) -> Vec<(MessageId, usize)> {
+        tokens
+            .iter()
+            .zip((1..=first).rev())
+            .map(|(tokens, id)| (MessageId::new(id), *tokens))
+            .collect()
+    }
+
+    #[test]
+    fn empty_history() {
+        assert_eq!(
+            select_history(&[], None),
+            HistorySelection {
+                recent: 0,
+                pending: 0,
+                refresh_through: None
+            }
+        );
+    }
+
+    #[test]
+    fn newest_message_is_kept_over_budget() {
+        let history = entries(10, &[CONTEXT_TOKEN_BUDGET * 2]);
+        assert_eq!(
+            select_history(&history, None),
+            HistorySelection {
+                recent: 1,
+                pending: 0,
+                refresh_through: None
+            }
+        );
+    }
+
+    #[test]
+    fn few_older_tokens_pass_through() {
+        let history = entries(10, &[CONTEXT_TOKEN_BUDGET - 100, 200, 300]);
+        assert_eq!(
+            select_history(&history, None),
+            HistorySelection {
+                recent: 1,
+                pending: 2,
+                refresh_through: None
+            }
+        );
+    }
+
+    #[test]
+    fn enough_older_tokens_refresh_through_the_newest() {
+        let history = entries(10, &[CONTEXT_TOKEN_BUDGET, 600, 600, 600]);
+        assert_eq!(
+            select_history(&history, None),
+            HistorySelection {
+                recent: 1,
+                pending: 3,
+                refresh_through: Some(MessageId::new(9))
+            }
+        );
+    }
+
+    #[test]
+    fn summarized_messages_are_left_out() {
+        let history = entries(10, &[CONTEXT_TOKEN_BUDGET, 600, 600, 600, 600]);
+        // Messages 7 and older are in the summary, 9 and 8 add up to a refresh.
+        assert_eq!(
+            select_history(&history, Some(MessageId::new(7))),
+            HistorySelection {
+                recent: 1,
+                pending: 2,
+                refresh_through: Some(MessageId::new(9))
+            }
+        );
+        assert_eq!(
+            select_history(&history, Some(MessageId::new(8))),
+            HistorySelection {
+                recent: 1,
+                pending: 1,
+                refresh_through: None
+            }
+        );
+        // A summary through the newest message leaves nothing pending.
+        assert_eq!(
+            select_history(&history, Some(MessageId::new(10))).pending,
+            0
+        );
+    }
+}
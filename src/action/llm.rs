@@ -0,0 +1,151 @@
+use crate::{
+    err::RKBServiceRequestErr,
+    llm::{self, PROVIDERS},
+    settings::LlmSelection,
+    split_action, RKBServiceRequest,
+};
+
+impl RKBServiceRequest {
+    /// Shows the chat provider in use, `use` or `channel` selects one and `reset` clears it.
+    pub async fn llm(&self) -> Result<(), RKBServiceRequestErr> {
+        let (subaction, argument) =
+            split_action(self.get_content().unwrap_or_default().to_string());
+        let argument = argument.trim();
+        match subaction.as_str() {
+            "use" | "guild" => self.select_llm(argument, false).await,
+            "channel" => self.select_llm(argument, true).await,
+            "reset" => self.reset_llm(argument == "channel").await,
+            "reasoning" => self.reasoning_visibility(argument).await,
+            _ => self.show_llm().await,
+        }
+    }
+
+    async fn show_llm(&self) -> Result<(), RKBServiceRequestErr> {
+        let settings = self.guild_settings();
+        let source = match settings
+            .channel_llm
+            .get(&self.msg.channel_id.to_string())
+            .and_then(|v| v.provider.as_ref())
+        {
+            Some(_) => "set for this channel",
+            None if settings.llm.provider.is_some() => "set for this server",
+            None => "bot default",
+        };
+        let selection = self.llm_selection();
+        let name = selection.provider.clone().unwrap_or_default();
+        let models = [(selection.model, false), (selection.reasoning_model, true)].map(
+            |(model, reasoning)| match llm::provider(&self.tkn, &name, model, reasoning) {
+                Ok(provider) => format!("`{}`", provider.model()),
+                Err(_) => String::from("unavailable"),
+            },
+        );
+        self.try_send_message(format!(
+            "🤖 Chat uses **{}** · {} · reasoning {} ({}).",
+            name, models[0], models[1], source
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// `<provider> [model] [reasoning model]`, for the guild or this channel.
+    async fn select_llm(&self, argument: &str, channel: bool) -> Result<(), RKBServiceRequestErr> {
+        if !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only a moderator can change the chat provider. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let mut words = argument.split_whitespace();
+        let provider = words.next().unwrap_or_default().to_lowercase();
+        if !PROVIDERS.contains(&provider.as_str()) {
+            self.try_send_message(format!(
+                "Pick a provider out of {}. 🤖",
+                PROVIDERS.map(|v| format!("`{}`", v)).join(", ")
+            ))
+            .await?;
+            return Ok(());
+        }
+        let selection = LlmSelection {
+            provider: Some(provider),
+            model: words.next().map(str::to_string),
+            reasoning_model: words.next().map(str::to_string),
+        };
+        let channel_id = self.msg.channel_id.to_string();
+        let updated = self.update_guild_settings(|settings| match channel {
+            true => settings.channel_llm.insert(channel_id, selection),
+            false => Some(std::mem::replace(&mut settings.llm, selection)),
+        });
+        if let Err(RKBServiceRequestErr::Settings(err)) = updated {
+            self.try_send_message(format!("{}. 🤖", err)).await?;
+            return Ok(());
+        }
+        updated?;
+        self.show_llm().await
+    }
+
+    /// `show` or `hide` the chain of thought in `reason` answers for the guild.
+    async fn reasoning_visibility(&self, argument: &str) -> Result<(), RKBServiceRequestErr> {
+        let hide_reasoning = match argument {
+            "show" => false,
+            "hide" => true,
+            _ => {
+                let state = reasoning_state(self.guild_settings().hide_reasoning);
+                self.try_send_message(format!(
+                    "Reasoning is {} in answers here, `show` or `hide` it. 💭",
+                    state
+                ))
+                .await?;
+                return Ok(());
+            }
+        };
+        if !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only a moderator can change whether reasoning is shown. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let updated =
+            self.update_guild_settings(|settings| settings.hide_reasoning = hide_reasoning);
+        if let Err(RKBServiceRequestErr::Settings(err)) = updated {
+            self.try_send_message(format!("{}. 💭", err)).await?;
+            return Ok(());
+        }
+        updated?;
+        self.try_send_message(format!(
+            "Reasoning will be {} in answers here. 💭",
+            reasoning_state(hide_reasoning)
+        ))
+        .await?;
+        Ok(())
+    }
+
+    async fn reset_llm(&self, channel: bool) -> Result<(), RKBServiceRequestErr> {
+        if !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only a moderator can change the chat provider. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let channel_id = self.msg.channel_id.to_string();
+        let updated = self.update_guild_settings(|settings| match channel {
+            true => settings.channel_llm.remove(&channel_id),
+            false => Some(std::mem::take(&mut settings.llm)),
+        });
+        if let Err(RKBServiceRequestErr::Settings(err)) = updated {
+            self.try_send_message(format!("{}. 🤖", err)).await?;
+            return Ok(());
+        }
+        updated?;
+        self.show_llm().await
+    }
+}
+
+fn reasoning_state(hide_reasoning: bool) -> &'static str {
+    match hide_reasoning {
+        true => "hidden",
+        false => "shown",
+    }
+}
@@ -1,5 +1,18 @@
-pub mod deepseek;
+pub mod almanac;
+pub mod aqi;
+pub mod attachment;
+pub mod chat;
+pub mod geo;
 pub mod help;
+pub mod history;
+pub mod llm;
+pub mod persona;
+pub mod pomodoro;
+pub mod recurring;
+pub mod remind;
+pub mod stopwatch;
 pub mod test;
 pub mod timer;
+pub mod tools;
+pub mod usage;
 pub mod weather;
@@ -0,0 +1,274 @@
+use serde::{Deserialize, Serialize};
+use serenity::all::{CreateWebhook, Webhook};
+use thiserror::Error;
+
+use crate::{err::RKBServiceRequestErr, split_action, RKBServiceRequest};
+
+const PERSONA_WEBHOOK_NAME: &str = "RustyKelvinBot personas";
+const MAX_PERSONA_NAME_LENGTH: usize = 32;
+const MAX_PERSONA_PROMPT_LENGTH: usize = 4000;
+const UNBIND_ARGUMENTS: [&str; 3] = ["none", "off", "default"];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to get or create the persona webhook")]
+    WebhookUnavailable,
+}
+
+/// A named system prompt for chat, bound to channels by guild admins.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Persona {
+    pub name: String,
+    pub prompt: String,
+    /// Reply through a webhook under the persona's name instead of as the bot.
+    #[serde(default)]
+    pub webhook: bool,
+    pub avatar_url: Option<String>,
+}
+
+impl RKBServiceRequest {
+    /// `create|edit NAME PROMPT`, `use NAME|none`, `webhook NAME on|off [AVATAR URL]`, `list`, or
+    /// `show [NAME]`.
+    pub async fn persona(&self) -> Result<(), RKBServiceRequestErr> {
+        let (subaction, argument) =
+            split_action(self.get_content().unwrap_or_default().to_string());
+        let argument = argument.trim();
+        let editing = matches!(subaction.as_str(), "create" | "edit" | "use" | "webhook");
+        if editing && !self.is_admin() {
+            self.try_send_message(String::from("Only a server admin can change personas. 🔒"))
+                .await?;
+            return Ok(());
+        }
+        let reply = match subaction.as_str() {
+            "create" => self.save_persona(argument, false)?,
+            "edit" => self.save_persona(argument, true)?,
+            "use" => self.bind_persona(argument)?,
+            "webhook" => self.persona_delivery(argument)?,
+            "list" => self.persona_list(),
+            "show" => self.persona_details(argument),
+            _ => self.persona_details(""),
+        };
+        self.try_send_message(reply).await?;
+        Ok(())
+    }
+
+    /// The persona bound to this channel, or to the channel a thread was started in.
+    pub fn channel_persona(&self) -> Option<Persona> {
+        let mut settings = self.guild_settings();
+        let name = settings
+            .channel_personas
+            .get(&self.msg.channel_id.to_string())
+            .or_else(|| {
+                settings
+                    .channel_personas
+                    .get(&self.parent_channel()?.to_string())
+            })?
+            .clone();
+        settings.personas.remove(&name)
+    }
+
+    /// The bot's persona webhook in this channel, created when missing.
+    pub async fn persona_webhook(&self) -> Result<Webhook, RKBServiceRequestErr> {
+        let channel_id = self.parent_channel().unwrap_or(self.msg.channel_id);
+        let bot_userid = self.ctx.cache.current_user().id;
+        let existing = channel_id
+            .webhooks(&self.ctx.http)
+            .await
+            .map_err(|_| Error::WebhookUnavailable)?
+            .into_iter()
+            .find(|v| {
+                v.user.as_ref().is_some_and(|v| v.id == bot_userid)
+                    && v.name.as_deref() == Some(PERSONA_WEBHOOK_NAME)
+                    && v.token.is_some()
+            });
+        let webhook = match existing {
+            Some(webhook) => webhook,
+            None => channel_id
+                .create_webhook(&self.ctx.http, CreateWebhook::new(PERSONA_WEBHOOK_NAME))
+                .await
+                .map_err(|_| Error::WebhookUnavailable)?,
+        };
+        // Remembered so chat history counts the webhook's messages as the bot's.
+        let webhook_id = webhook.id;
+        if self
+            .guild_settings()
+            .persona_webhooks
+            .get(&channel_id.to_string())
+            != Some(&webhook_id)
+        {
+            self.update_guild_settings(|settings| {
+                settings
+                    .persona_webhooks
+                    .insert(channel_id.to_string(), webhook_id)
+            })?;
+        }
+        Ok(webhook)
+    }
+
+    fn save_persona(&self, argument: &str, edit: bool) -> Result<String, RKBServiceRequestErr> {
+        let (name, prompt) = split_action(argument.to_string());
+        let prompt = prompt.trim().to_string();
+        if name.is_empty() || prompt.is_empty() {
+            return Ok(String::from("Give the persona a name and a prompt. 🎭"));
+        }
+        if name.chars().count() > MAX_PERSONA_NAME_LENGTH
+            || !name
+                .chars()
+                .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+        {
+            return Ok(format!(
+                "Persona names are up to {} letters, digits, `-` or `_`. 🎭",
+                MAX_PERSONA_NAME_LENGTH
+            ));
+        }
+        if prompt.chars().count() > MAX_PERSONA_PROMPT_LENGTH {
+            return Ok(format!(
+                "Persona prompts are up to {} characters. 🎭",
+                MAX_PERSONA_PROMPT_LENGTH
+            ));
+        }
+        let key = name.to_lowercase();
+        let saved =
+            self.update_guild_settings(|settings| match (settings.personas.get_mut(&key), edit) {
+                (Some(_), false) | (None, true) => false,
+                (Some(persona), true) => {
+                    persona.prompt = prompt;
+                    true
+                }
+                (None, false) => {
+                    settings.personas.insert(
+                        key,
+                        Persona {
+                            name: name.clone(),
+                            prompt,
+                            webhook: false,
+                            avatar_url: None,
+                        },
+                    );
+                    true
+                }
+            });
+        let reply = match (saved, edit) {
+            (Err(RKBServiceRequestErr::Settings(err)), _) => format!("{}. 🎭", err),
+            (Err(err), _) => return Err(err),
+            (Ok(false), false) => format!("Persona `{}` exists, `edit` it instead. 🎭", name),
+            (Ok(false), true) => format!("No persona named `{}`. 🎭", name),
+            (Ok(true), false) => format!("Persona `{}` created, `use {}` it here. 🎭", name, name),
+            (Ok(true), true) => format!("Persona `{}` updated. 🎭", name),
+        };
+        Ok(reply)
+    }
+
+    fn bind_persona(&self, argument: &str) -> Result<String, RKBServiceRequestErr> {
+        let key = argument.to_lowercase();
+        let channel_id = self.msg.channel_id.to_string();
+        let unbind = UNBIND_ARGUMENTS.contains(&key.as_str());
+        let bound = self.update_guild_settings(|settings| {
+            if unbind {
+                settings.channel_personas.remove(&channel_id);
+                return None;
+            }
+            let name = settings.personas.get(&key)?.name.clone();
+            settings.channel_personas.insert(channel_id, key);
+            Some(name)
+        });
+        let reply = match bound {
+            Err(RKBServiceRequestErr::Settings(err)) => format!("{}. 🎭", err),
+            Err(err) => return Err(err),
+            Ok(Some(name)) => format!("Chat here now speaks as `{}`. 🎭", name),
+            Ok(None) if unbind => String::from("Chat here is back to the default prompt. 🎭"),
+            Ok(None) => format!("No persona named `{}`, see `persona list`. 🎭", argument),
+        };
+        Ok(reply)
+    }
+
+    /// Turns webhook delivery on or off, optionally with an avatar.
+    fn persona_delivery(&self, argument: &str) -> Result<String, RKBServiceRequestErr> {
+        let mut words = argument.split_whitespace();
+        let (Some(name), Some(state)) = (words.next(), words.next()) else {
+            return Ok(String::from("Use `webhook NAME on|off [AVATAR URL]`. 🎭"));
+        };
+        let webhook = match state {
+            "on" => true,
+            "off" => false,
+            _ => return Ok(String::from("Use `webhook NAME on|off [AVATAR URL]`. 🎭")),
+        };
+        let avatar_url = words.next().map(str::to_string);
+        if avatar_url
+            .as_deref()
+            .is_some_and(|v| !v.starts_with("https://"))
+        {
+            return Ok(String::from("Avatars need an `https://` image URL. 🎭"));
+        }
+        let key = name.to_lowercase();
+        let updated = self.update_guild_settings(|settings| {
+            let persona = settings.personas.get_mut(&key)?;
+            persona.webhook = webhook;
+            if avatar_url.is_some() {
+                persona.avatar_url = avatar_url;
+            }
+            Some(persona.name.clone())
+        });
+        let reply = match updated {
+            Err(RKBServiceRequestErr::Settings(err)) => format!("{}. 🎭", err),
+            Err(err) => return Err(err),
+            Ok(Some(name)) if webhook => {
+                format!("`{}` now replies under its own name and avatar. 🎭", name)
+            }
+            Ok(Some(name)) => format!("`{}` now replies as the bot. 🎭", name),
+            Ok(None) => format!("No persona named `{}`. 🎭", name),
+        };
+        Ok(reply)
+    }
+
+    fn persona_list(&self) -> String {
+        let settings = self.guild_settings();
+        let mut personas = settings.personas.values().collect::<Vec<&Persona>>();
+        if personas.is_empty() {
+            return String::from("No personas yet, an admin can `persona create NAME PROMPT`. 🎭");
+        }
+        personas.sort_by_key(|v| v.name.to_lowercase());
+        let lines = personas
+            .into_iter()
+            .map(|persona| {
+                let key = persona.name.to_lowercase();
+                let channels = settings
+                    .channel_personas
+                    .iter()
+                    .filter(|(_, name)| **name == key)
+                    .map(|(channel_id, _)| format!("<#{}>", channel_id))
+                    .collect::<Vec<String>>();
+                match channels.is_empty() {
+                    true => format!("`{}`", persona.name),
+                    false => format!("`{}` · {}", persona.name, channels.join(" ")),
+                }
+            })
+            .collect::<Vec<String>>();
+        format!("🎭 Personas\n{}", lines.join("\n"))
+    }
+
+    /// The named persona, or the one bound to this channel.
+    fn persona_details(&self, name: &str) -> String {
+        let persona = match name.is_empty() {
+            true => self.channel_persona(),
+            false => self.guild_settings().personas.remove(&name.to_lowercase()),
+        };
+        let Some(persona) = persona else {
+            return match name.is_empty() {
+                true => String::from("No persona here, chat uses the default prompt. 🎭"),
+                false => format!("No persona named `{}`. 🎭", name),
+            };
+        };
+        let delivery = match persona.webhook {
+            true => " · webhook",
+            false => "",
+        };
+        let prompt = persona
+            .prompt
+            .lines()
+            .map(|v| format!("> {}", v))
+            .collect::<Vec<String>>()
+            .join("\n");
+        format!("🎭 `{}`{}\n{}", persona.name, delivery, prompt)
+    }
+}
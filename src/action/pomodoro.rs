@@ -0,0 +1,320 @@
+use std::time::Duration;
+
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context, EditMessage, MessageId, UserId};
+use tracing::error;
+
+use crate::{
+    action::timer::{TimerStore, TIMERS_DOCUMENT},
+    err::RKBServiceRequestErr,
+    resource::Resources,
+    split_action,
+    text::duration::{self, format_duration, parse_duration},
+    RKBServiceRequest,
+};
+
+const DEFAULT_WORK: Duration = Duration::from_secs(25 * 60);
+const DEFAULT_BREAK: Duration = Duration::from_secs(5 * 60);
+const DEFAULT_CYCLES: u32 = 4;
+const MAX_CYCLES: u32 = 12;
+
+/// Alternating work and break phases, ending after the last work phase.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Pomodoro {
+    pub id: u64,
+    pub channel_id: ChannelId,
+    pub author_id: UserId,
+    pub started: DateTime<Utc>,
+    /// Work phase length, seconds.
+    pub work: u64,
+    /// Break phase length, seconds.
+    pub rest: u64,
+    pub cycles: u32,
+    pub status_message_id: Option<MessageId>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    Work(u32),
+    Break(u32),
+    Done,
+}
+
+impl Pomodoro {
+    /// Total length of the work and break phases, `None` when it doesn't fit a `TimeDelta`.
+    pub fn length(&self) -> Option<TimeDelta> {
+        let cycles = u64::from(self.cycles);
+        let seconds = cycles
+            .checked_mul(self.work)?
+            .checked_add(cycles.saturating_sub(1).checked_mul(self.rest)?)?;
+        TimeDelta::try_seconds(i64::try_from(seconds).ok()?)
+    }
+
+    pub fn end(&self) -> DateTime<Utc> {
+        self.length()
+            .and_then(|v| self.started.checked_add_signed(v))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Phase at `now` and when it ends.
+    pub fn phase(&self, now: DateTime<Utc>) -> (Phase, DateTime<Utc>) {
+        let cycle = self.work.saturating_add(self.rest).max(1);
+        let elapsed = (now - self.started).num_seconds().max(0) as u64;
+        let index = elapsed / cycle;
+        let cycle_start = index.saturating_mul(cycle);
+        match (index + 1, elapsed % cycle) {
+            (number, _) if number > u64::from(self.cycles) => (Phase::Done, self.end()),
+            (number, within) if within < self.work => (
+                Phase::Work(number as u32),
+                self.after(cycle_start.saturating_add(self.work)),
+            ),
+            (number, _) if number == u64::from(self.cycles) => (Phase::Done, self.end()),
+            (number, _) => (
+                Phase::Break(number as u32),
+                self.after(cycle_start.saturating_add(cycle)),
+            ),
+        }
+    }
+
+    /// `seconds` after the start, the latest representable time when that overflows.
+    fn after(&self, seconds: u64) -> DateTime<Utc> {
+        i64::try_from(seconds)
+            .ok()
+            .and_then(TimeDelta::try_seconds)
+            .and_then(|v| self.started.checked_add_signed(v))
+            .unwrap_or(DateTime::<Utc>::MAX_UTC)
+    }
+
+    /// Status message text for the phase at `now`.
+    pub fn status(&self, now: DateTime<Utc>) -> String {
+        match self.phase(now) {
+            (Phase::Work(number), end) => format!(
+                "🍅 Pomodoro `#{}` · work {}/{} · break <t:{}:R>",
+                self.id,
+                number,
+                self.cycles,
+                end.timestamp()
+            ),
+            (Phase::Break(number), end) => format!(
+                "☕ Pomodoro `#{}` · break {}/{} · back to work <t:{}:R>",
+                self.id,
+                number,
+                self.cycles - 1,
+                end.timestamp()
+            ),
+            (Phase::Done, _) => format!(
+                "✅ Pomodoro `#{}` done, {} × {} of work.",
+                self.id,
+                self.cycles,
+                format_duration(Duration::from_secs(self.work))
+            ),
+        }
+    }
+
+    /// Notification mentioning the author when a phase starts.
+    fn announcement(&self, phase: Phase) -> String {
+        match phase {
+            Phase::Work(number) => format!(
+                "🍅 <@{}> Back to work, {}/{}.",
+                self.author_id, number, self.cycles
+            ),
+            Phase::Break(_) => format!(
+                "☕ <@{}> Break time, {}.",
+                self.author_id,
+                format_duration(Duration::from_secs(self.rest))
+            ),
+            Phase::Done => format!("✅ <@{}> Pomodoro done!", self.author_id),
+        }
+    }
+}
+
+impl RKBServiceRequest {
+    /// Starts a pomodoro, `[work] [break] [cycles]` with bare numbers read as minutes.
+    pub async fn pomodoro(&self) -> Result<(), RKBServiceRequestErr> {
+        let content = self.get_content().unwrap_or_default();
+        let (subaction, argument) = split_action(content.to_string());
+        match subaction.as_str() {
+            "cancel" | "stop" => return self.cancel_timer(&argument).await,
+            "list" => return self.timers().await,
+            _ => (),
+        }
+        let tokens = content.split_whitespace().collect::<Vec<&str>>();
+        let lengths = [(0, DEFAULT_WORK), (1, DEFAULT_BREAK)].map(|(i, default)| {
+            tokens
+                .get(i)
+                .map_or(Ok(default), |token| phase_length(token))
+        });
+        let (work, rest) = match lengths {
+            [Ok(work), Ok(rest)] if work.as_secs() > 0 => (work, rest),
+            [Err(err), _] | [_, Err(err)] => {
+                self.try_send_message(format!("{}. 🍅", err)).await?;
+                return Ok(());
+            }
+            _ => {
+                self.try_send_message(String::from("Work phases need some length. 🍅"))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let cycles = match tokens.get(2).map(|v| v.parse::<u32>()) {
+            None => DEFAULT_CYCLES,
+            Some(Ok(cycles)) if (1..=MAX_CYCLES).contains(&cycles) => cycles,
+            Some(_) => {
+                self.try_send_message(format!("Cycles must be between 1 and {}. 🍅", MAX_CYCLES))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let mut pomodoro = Pomodoro {
+            id: 0,
+            channel_id: self.msg.channel_id,
+            author_id: self.msg.author.id,
+            started: Utc::now(),
+            work: work.as_secs(),
+            rest: rest.as_secs(),
+            cycles,
+            status_message_id: None,
+        };
+        let within_max = pomodoro
+            .length()
+            .and_then(|v| v.to_std().ok())
+            .is_some_and(|v| v <= self.max_timer());
+        if !within_max {
+            self.try_send_message(format!(
+                "Timers can run for up to {} here. ⏳",
+                format_duration(self.max_timer())
+            ))
+            .await?;
+            return Ok(());
+        }
+        pomodoro.id = self
+            .rsc
+            .storage
+            .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                store.next_id += 1;
+                store.next_id
+            })?;
+        let status_message = self
+            .try_send_message(pomodoro.status(pomodoro.started))
+            .await?;
+        pomodoro.status_message_id = Some(status_message.id);
+        self.rsc
+            .storage
+            .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                store.pomodoros.push(pomodoro.clone())
+            })?;
+        arm_pomodoro(self.ctx.clone(), self.rsc.clone(), pomodoro);
+        Ok(())
+    }
+}
+
+/// A bare number of minutes or a duration.
+fn phase_length(token: &str) -> Result<Duration, duration::Error> {
+    if let Ok(minutes) = token.parse::<u64>() {
+        return Ok(Duration::from_secs(minutes.saturating_mul(60)));
+    }
+    let (delta, _) = parse_duration(&[token])?;
+    Ok(delta.to_std().unwrap_or_default())
+}
+
+/// Spawns the task stepping through the phases and registers its cancellation handle.
+pub fn arm_pomodoro(ctx: Context, rsc: Resources, pomodoro: Pomodoro) {
+    let registry = rsc.timers.clone();
+    let id = pomodoro.id;
+    registry.spawn(id, async move {
+        if let Err(err) = run_pomodoro(ctx, rsc.clone(), pomodoro).await {
+            error!("Failed to run pomodoro: {:?}", err);
+        }
+        rsc.timers.remove(id);
+    });
+}
+
+/// Edits the status message and mentions the author at each phase change.
+async fn run_pomodoro(
+    ctx: Context,
+    rsc: Resources,
+    pomodoro: Pomodoro,
+) -> Result<(), RKBServiceRequestErr> {
+    let mut current = None;
+    loop {
+        let now = Utc::now();
+        let (phase, end) = pomodoro.phase(now);
+        if current != Some(phase) {
+            if let Some(status_message_id) = pomodoro.status_message_id {
+                let builder = EditMessage::new().content(pomodoro.status(now));
+                if let Err(err) = pomodoro
+                    .channel_id
+                    .edit_message(&ctx.http, status_message_id, builder)
+                    .await
+                {
+                    error!("Failed to update pomodoro status: {:?}", err);
+                }
+            }
+            // Phases that passed while the bot was down are not announced.
+            if current.is_some() {
+                let announcement = pomodoro.announcement(phase);
+                pomodoro
+                    .channel_id
+                    .say(&ctx.http, &announcement)
+                    .await
+                    .map_err(|_| RKBServiceRequestErr::DiscordMessageSendFailure(announcement))?;
+            }
+            current = Some(phase);
+        }
+        if phase == Phase::Done {
+            break;
+        }
+        tokio::time::sleep((end - Utc::now()).to_std().unwrap_or_default()).await;
+    }
+    rsc.storage
+        .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+            store.pomodoros.retain(|v| v.id != pomodoro.id)
+        })?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    fn with_phases(work: u64, rest: u64, cycles: u32) -> Pomodoro {
+        Pomodoro {
+            id: 1,
+            channel_id: ChannelId::new(1),
+            author_id: UserId::new(1),
+            started: Utc.with_ymd_and_hms(2024, 3, 6, 9, 0, 0).unwrap(),
+            work,
+            rest,
+            cycles,
+            status_message_id: None,
+        }
+    }
+
+    #[test]
+    fn phases_alternate() {
+        let pomodoro = with_phases(25 * 60, 5 * 60, 2);
+        let at = |minutes| pomodoro.started + TimeDelta::minutes(minutes);
+        assert_eq!(pomodoro.length(), Some(TimeDelta::minutes(55)));
+        assert_eq!(pomodoro.phase(at(0)), (Phase::Work(1), at(25)));
+        assert_eq!(pomodoro.phase(at(27)), (Phase::Break(1), at(30)));
+        assert_eq!(pomodoro.phase(at(31)), (Phase::Work(2), at(55)));
+        assert_eq!(pomodoro.phase(at(56)), (Phase::Done, at(55)));
+    }
+
+    #[test]
+    fn huge_lengths_do_not_panic() {
+        let seconds = phase_length("1000000000000000").unwrap().as_secs();
+        let pomodoro = with_phases(seconds, seconds, MAX_CYCLES);
+        assert_eq!(pomodoro.length(), None);
+        assert_eq!(pomodoro.end(), DateTime::<Utc>::MAX_UTC);
+        let (phase, end) = pomodoro.phase(pomodoro.started);
+        assert_eq!(phase, Phase::Work(1));
+        assert_eq!(end, DateTime::<Utc>::MAX_UTC);
+        let saturated = with_phases(u64::MAX, u64::MAX, 1);
+        assert_eq!(saturated.length(), None);
+        assert_eq!(saturated.phase(saturated.started).0, Phase::Work(1));
+    }
+}
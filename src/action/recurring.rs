@@ -0,0 +1,287 @@
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, Context, CreateAllowedMentions, CreateMessage, UserId};
+use tracing::error;
+
+use crate::{
+    action::remind::Error,
+    breakdown_string,
+    err::RKBServiceRequestErr,
+    recurrence::{next_occurrence, Recurrence},
+    resource::Resources,
+    RKBServiceRequest,
+};
+
+const RECURRING_DOCUMENT: &str = "recurring";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecurringReminder {
+    pub id: u64,
+    pub channel_id: ChannelId,
+    pub author_id: UserId,
+    /// Six field cron expression, evaluated in `timezone`.
+    pub schedule: String,
+    pub label: String,
+    pub timezone: String,
+    pub message: String,
+    pub paused: bool,
+    pub subscribers: Vec<UserId>,
+}
+
+/// Recurring reminders, persisted so they survive restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct RecurringStore {
+    next_id: u64,
+    reminders: Vec<RecurringReminder>,
+}
+
+impl RecurringReminder {
+    fn tz(&self) -> Tz {
+        self.timezone.parse::<Tz>().unwrap_or(Tz::UTC)
+    }
+}
+
+impl RKBServiceRequest {
+    /// Creates a reminder from `every ...` or `cron "..."` in the author's timezone.
+    pub(crate) async fn recurring_reminder(
+        &self,
+        kind: &str,
+        argument: &str,
+    ) -> Result<(), RKBServiceRequestErr> {
+        let parsed = match kind {
+            "cron" => Recurrence::cron(argument),
+            _ => Recurrence::every(argument),
+        };
+        let (recurrence, message) = match parsed {
+            Ok(v) => v,
+            Err(err) => {
+                self.try_send_message(format!("Could not read that schedule, {}. 🗓️", err))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let tz = self.user_timezone();
+        let Some(next) = next_occurrence(&recurrence.expression, tz, Utc::now()) else {
+            self.try_send_message(String::from("That schedule never comes up. 🗓️"))
+                .await?;
+            return Ok(());
+        };
+        let mut reminder = RecurringReminder {
+            id: 0,
+            channel_id: self.msg.channel_id,
+            author_id: self.msg.author.id,
+            schedule: recurrence.expression,
+            label: recurrence.label,
+            timezone: tz.name().to_string(),
+            message,
+            paused: false,
+            subscribers: vec![self.msg.author.id],
+        };
+        reminder.id =
+            self.rsc
+                .storage
+                .update(RECURRING_DOCUMENT, |store: &mut RecurringStore| {
+                    store.next_id += 1;
+                    store.reminders.push(RecurringReminder {
+                        id: store.next_id,
+                        ..reminder.clone()
+                    });
+                    store.next_id
+                })?;
+        self.try_send_message(format!(
+            "Reminder `#{}` set {} ({}), next <t:{}:F>. 🔁",
+            reminder.id,
+            reminder.label,
+            reminder.timezone,
+            next.timestamp()
+        ))
+        .await?;
+        arm_reminder(self.ctx.clone(), self.rsc.clone(), reminder.id);
+        Ok(())
+    }
+
+    /// Lists the recurring reminders of this channel.
+    pub(crate) async fn recurring_reminders(&self) -> Result<(), RKBServiceRequestErr> {
+        let store = self.rsc.storage.load::<RecurringStore>(RECURRING_DOCUMENT);
+        let now = Utc::now();
+        let lines = store
+            .reminders
+            .iter()
+            .filter(|v| v.channel_id == self.msg.channel_id)
+            .map(|v| {
+                let mut line = format!("`#{}` {} ({})", v.id, v.label, v.timezone);
+                match (v.paused, next_occurrence(&v.schedule, v.tz(), now)) {
+                    (true, _) => line += " · ⏸️ paused",
+                    (false, Some(next)) => line += &format!(" · next <t:{}:R>", next.timestamp()),
+                    (false, None) => (),
+                }
+                line += &format!(" · {} subscribed", v.subscribers.len());
+                if !v.message.is_empty() {
+                    line += &format!(" · {}", v.message);
+                }
+                line
+            })
+            .collect::<Vec<String>>();
+        let response = match lines.is_empty() {
+            true => String::from("No recurring reminders. 🔁"),
+            false => format!(">>> {}", lines.join("\n")),
+        };
+        self.try_send_message(response).await?;
+        Ok(())
+    }
+
+    /// Handles `pause`, `resume`, `delete`, `subscribe` and `unsubscribe` for a reminder id.
+    pub(crate) async fn manage_reminder(
+        &self,
+        subaction: &str,
+        argument: &str,
+    ) -> Result<(), RKBServiceRequestErr> {
+        let id = argument
+            .trim()
+            .trim_start_matches('#')
+            .parse::<u64>()
+            .map_err(|_| Error::InvalidReminderId(argument.to_string()))?;
+        let store = self.rsc.storage.load::<RecurringStore>(RECURRING_DOCUMENT);
+        let Some(reminder) = store.reminders.into_iter().find(|v| v.id == id) else {
+            self.try_send_message(format!("No recurring reminder `#{}`. 🎣", id))
+                .await?;
+            return Ok(());
+        };
+        let subscription = matches!(subaction, "subscribe" | "unsubscribe");
+        if !subscription && reminder.author_id != self.msg.author.id && !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only the reminder's author or a moderator can change it. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let user_id = self.msg.author.id;
+        let update = |f: fn(&mut RecurringStore, u64, UserId)| {
+            self.rsc
+                .storage
+                .update(RECURRING_DOCUMENT, |store: &mut RecurringStore| {
+                    f(store, id, user_id)
+                })
+        };
+        let response = match subaction {
+            "pause" => {
+                if let Some(handle) = self.rsc.reminders.remove(id) {
+                    handle.abort();
+                }
+                update(|store, id, _| {
+                    store
+                        .reminders
+                        .iter_mut()
+                        .filter(|v| v.id == id)
+                        .for_each(|v| v.paused = true)
+                })?;
+                format!("Reminder `#{}` paused. ⏸️", id)
+            }
+            "resume" => {
+                update(|store, id, _| {
+                    store
+                        .reminders
+                        .iter_mut()
+                        .filter(|v| v.id == id)
+                        .for_each(|v| v.paused = false)
+                })?;
+                if !self.rsc.reminders.contains(id) {
+                    arm_reminder(self.ctx.clone(), self.rsc.clone(), id);
+                }
+                format!("Reminder `#{}` resumed. ▶️", id)
+            }
+            "subscribe" => {
+                update(|store, id, user_id| {
+                    store
+                        .reminders
+                        .iter_mut()
+                        .filter(|v| v.id == id && !v.subscribers.contains(&user_id))
+                        .for_each(|v| v.subscribers.push(user_id))
+                })?;
+                format!("Subscribed to reminder `#{}`. 🔔", id)
+            }
+            "unsubscribe" => {
+                update(|store, id, user_id| {
+                    store
+                        .reminders
+                        .iter_mut()
+                        .filter(|v| v.id == id)
+                        .for_each(|v| v.subscribers.retain(|subscriber| *subscriber != user_id))
+                })?;
+                format!("Unsubscribed from reminder `#{}`. 🔕", id)
+            }
+            _ => {
+                if let Some(handle) = self.rsc.reminders.remove(id) {
+                    handle.abort();
+                }
+                update(|store, id, _| store.reminders.retain(|v| v.id != id))?;
+                format!("Reminder `#{}` deleted. 🗑️", id)
+            }
+        };
+        self.try_send_message(response).await?;
+        Ok(())
+    }
+}
+
+/// Spawns the task posting a reminder on each occurrence and registers its cancellation handle.
+pub fn arm_reminder(ctx: Context, rsc: Resources, id: u64) {
+    let registry = rsc.reminders.clone();
+    registry.spawn(id, async move {
+        run_reminder(ctx, rsc.clone(), id).await;
+        rsc.reminders.remove(id);
+    });
+}
+
+/// Sleeps until each occurrence and posts the message to the subscribers, until paused or deleted.
+async fn run_reminder(ctx: Context, rsc: Resources, id: u64) {
+    let load = || {
+        rsc.storage
+            .load::<RecurringStore>(RECURRING_DOCUMENT)
+            .reminders
+            .into_iter()
+            .find(|v| v.id == id && !v.paused)
+    };
+    loop {
+        let Some(reminder) = load() else {
+            return;
+        };
+        let Some(next) = next_occurrence(&reminder.schedule, reminder.tz(), Utc::now()) else {
+            return;
+        };
+        tokio::time::sleep((next - Utc::now()).to_std().unwrap_or_default()).await;
+        // Subscribers and state may have changed while sleeping.
+        let Some(reminder) = load() else {
+            return;
+        };
+        let mentions = reminder
+            .subscribers
+            .iter()
+            .map(|v| format!("<@{}>", v))
+            .collect::<Vec<String>>()
+            .join(" ");
+        let message = match reminder.message.is_empty() {
+            true => &reminder.label,
+            false => &reminder.message,
+        };
+        let allowed_mentions = CreateAllowedMentions::new().users(reminder.subscribers.clone());
+        for chunk in breakdown_string(format!("🔁 {}\n{}", message, mentions)) {
+            let builder = CreateMessage::new()
+                .content(chunk)
+                .allowed_mentions(allowed_mentions.clone());
+            if let Err(err) = reminder.channel_id.send_message(&ctx.http, builder).await {
+                error!("Failed to post recurring reminder: {:?}", err);
+            }
+        }
+    }
+}
+
+/// Arms persisted reminders that are not paused or already running.
+pub fn rearm_reminders(ctx: Context, rsc: Resources) {
+    let store = rsc.storage.load::<RecurringStore>(RECURRING_DOCUMENT);
+    for reminder in store.reminders {
+        if !reminder.paused && !rsc.reminders.contains(reminder.id) {
+            arm_reminder(ctx.clone(), rsc.clone(), reminder.id);
+        }
+    }
+}
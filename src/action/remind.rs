@@ -0,0 +1,102 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::{
+    action::timer::Timer, err::RKBServiceRequestErr, split_action, split_flags,
+    text::natural_time::parse_natural_time, RKBServiceRequest,
+};
+
+const TIMEZONES_DOCUMENT: &str = "timezones";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("timezone is not a known IANA name")]
+    UnknownTimezone(String),
+    #[error("reminder id is not a number")]
+    InvalidReminderId(String),
+}
+
+/// IANA timezone names chosen by each user, keyed by user id.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct TimezoneStore {
+    users: HashMap<String, String>,
+}
+
+impl RKBServiceRequest {
+    pub async fn remind(&self) -> Result<(), RKBServiceRequestErr> {
+        let content = self.get_content().unwrap_or_default();
+        let (subaction, argument) = split_action(content.to_string());
+        match subaction.as_str() {
+            "tz" | "timezone" => return self.reminder_timezone(argument.trim()).await,
+            "every" | "cron" => return self.recurring_reminder(&subaction, &argument).await,
+            "list" => return self.recurring_reminders().await,
+            "pause" | "resume" | "delete" | "subscribe" | "unsubscribe" => {
+                return self.manage_reminder(&subaction, &argument).await
+            }
+            _ => (),
+        }
+        let (content, flags) = split_flags(content);
+        let dm = flags.iter().any(|v| v == "dm");
+        let now = Utc::now();
+        let Some((deadline, recalled_message)) =
+            parse_natural_time(&content, now, self.user_timezone())
+        else {
+            self.try_send_message(String::from(
+                "Could not work out when to remind you. Try `tomorrow 9am`, `next friday 17:00` or `in 2 hours`. 🕰️",
+            ))
+            .await?;
+            return Ok(());
+        };
+        if deadline <= now {
+            self.try_send_message(format!("<t:{}:F> is in the past. ⌛", deadline.timestamp()))
+                .await?;
+            return Ok(());
+        }
+        let timer = Timer {
+            id: 0,
+            channel_id: self.msg.channel_id,
+            author_id: self.msg.author.id,
+            dob: now,
+            deadline,
+            recalled_message,
+            pinned_message_id: None,
+            dm,
+        };
+        self.start_timer(timer).await
+    }
+
+    /// The author's saved timezone, UTC when unset.
+    pub fn user_timezone(&self) -> Tz {
+        self.rsc
+            .storage
+            .load::<TimezoneStore>(TIMEZONES_DOCUMENT)
+            .users
+            .get(&self.msg.author.id.to_string())
+            .and_then(|v| v.parse::<Tz>().ok())
+            .unwrap_or(Tz::UTC)
+    }
+
+    async fn reminder_timezone(&self, timezone: &str) -> Result<(), RKBServiceRequestErr> {
+        if timezone.is_empty() {
+            self.try_send_message(format!("Your timezone is {}. 🌐", self.user_timezone()))
+                .await?;
+            return Ok(());
+        }
+        let tz = timezone
+            .parse::<Tz>()
+            .map_err(|_| Error::UnknownTimezone(timezone.to_string()))?;
+        let user_id = self.msg.author.id.to_string();
+        self.rsc
+            .storage
+            .update(TIMEZONES_DOCUMENT, |store: &mut TimezoneStore| {
+                store.users.insert(user_id, tz.name().to_string())
+            })?;
+        self.try_send_message(format!("Timezone set to {}. 🌐", tz))
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,160 @@
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{ChannelId, UserId};
+
+use crate::{
+    action::timer::{format_delta, TimerStore, TIMERS_DOCUMENT},
+    err::RKBServiceRequestErr,
+    split_action, RKBServiceRequest,
+};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Stopwatch {
+    pub id: u64,
+    pub channel_id: ChannelId,
+    pub author_id: UserId,
+    pub started: DateTime<Utc>,
+    pub laps: Vec<DateTime<Utc>>,
+    pub label: String,
+}
+
+impl Stopwatch {
+    pub fn elapsed(&self, now: DateTime<Utc>) -> TimeDelta {
+        (now - self.started).max(TimeDelta::zero())
+    }
+
+    /// Lap times followed by the total, as of `now`.
+    fn summary(&self, now: DateTime<Utc>) -> String {
+        let mut previous = self.started;
+        let mut lines = Vec::new();
+        for (i, lap) in self.laps.iter().enumerate() {
+            lines.push(format!("Lap {}: {}", i + 1, format_delta(*lap - previous)));
+            previous = *lap;
+        }
+        let mut total = format!(
+            "⏱️ Stopwatch `#{}` · {}",
+            self.id,
+            format_delta(self.elapsed(now))
+        );
+        if !self.label.is_empty() {
+            total += &format!(" · {}", self.label);
+        }
+        lines.push(total);
+        lines.join("\n")
+    }
+}
+
+impl RKBServiceRequest {
+    /// `start [label]`, `lap [id]`, `stop [id]`, or the elapsed time of a running stopwatch.
+    pub async fn stopwatch(&self) -> Result<(), RKBServiceRequestErr> {
+        let (subaction, argument) =
+            split_action(self.get_content().unwrap_or_default().to_string());
+        let argument = argument.trim();
+        match subaction.as_str() {
+            "start" => return self.start_stopwatch(argument).await,
+            "list" => return self.timers().await,
+            _ => (),
+        }
+        let store = self.rsc.storage.load::<TimerStore>(TIMERS_DOCUMENT);
+        let target = match subaction.as_str() {
+            "lap" | "stop" | "show" => argument,
+            _ => subaction.as_str(),
+        };
+        // Without an id, the author's latest stopwatch in this channel.
+        let stopwatch =
+            match target.trim_start_matches('#').parse::<u64>() {
+                Ok(id) => store.stopwatches.into_iter().find(|v| v.id == id),
+                Err(_) => store.stopwatches.into_iter().rev().find(|v| {
+                    v.channel_id == self.msg.channel_id && v.author_id == self.msg.author.id
+                }),
+            };
+        let Some(mut stopwatch) = stopwatch else {
+            self.try_send_message(String::from(
+                "No running stopwatch, start one with `stopwatch start`. ⏱️",
+            ))
+            .await?;
+            return Ok(());
+        };
+        let now = Utc::now();
+        let editing = matches!(subaction.as_str(), "lap" | "stop");
+        if editing && stopwatch.author_id != self.msg.author.id && !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only the stopwatch's author or a moderator can change it. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let id = stopwatch.id;
+        match subaction.as_str() {
+            "lap" => {
+                stopwatch.laps.push(now);
+                self.rsc
+                    .storage
+                    .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                        store
+                            .stopwatches
+                            .iter_mut()
+                            .filter(|v| v.id == id)
+                            .for_each(|v| v.laps.push(now))
+                    })?;
+                let previous = stopwatch
+                    .laps
+                    .iter()
+                    .rev()
+                    .nth(1)
+                    .copied()
+                    .unwrap_or(stopwatch.started);
+                self.try_send_message(format!(
+                    "⏱️ Lap {}: {} · total {}",
+                    stopwatch.laps.len(),
+                    format_delta(now - previous),
+                    format_delta(stopwatch.elapsed(now))
+                ))
+                .await?;
+            }
+            "stop" => {
+                self.rsc
+                    .storage
+                    .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                        store.stopwatches.retain(|v| v.id != id)
+                    })?;
+                self.try_send_message(format!(">>> {} · stopped 🛑", stopwatch.summary(now)))
+                    .await?;
+            }
+            _ => {
+                self.try_send_message(format!(">>> {}", stopwatch.summary(now)))
+                    .await?;
+            }
+        }
+        Ok(())
+    }
+
+    async fn start_stopwatch(&self, label: &str) -> Result<(), RKBServiceRequestErr> {
+        let mut stopwatch = Stopwatch {
+            id: 0,
+            channel_id: self.msg.channel_id,
+            author_id: self.msg.author.id,
+            started: Utc::now(),
+            laps: Vec::new(),
+            label: label.to_string(),
+        };
+        stopwatch.id = self
+            .rsc
+            .storage
+            .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                store.next_id += 1;
+                store.stopwatches.push(Stopwatch {
+                    id: store.next_id,
+                    ..stopwatch.clone()
+                });
+                store.next_id
+            })?;
+        self.try_send_message(format!(
+            "⏱️ Stopwatch `#{}` started <t:{}:R>.",
+            stopwatch.id,
+            stopwatch.started.timestamp()
+        ))
+        .await?;
+        Ok(())
+    }
+}
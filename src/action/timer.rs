@@ -1,60 +1,169 @@
-use std::{fmt::Display, time::Duration};
+use std::{
+    collections::{BTreeSet, HashMap},
+    fmt::Display,
+    future::Future,
+    sync::{atomic::Ordering, Mutex},
+    time::Duration,
+};
 
-use chrono::{DateTime, Local, TimeDelta, Utc};
+use chrono::{DateTime, TimeDelta, Utc};
+use serde::{Deserialize, Serialize};
+use serenity::all::{
+    parse_role_mention, parse_user_mention, ButtonStyle, Channel, ChannelId, ComponentInteraction,
+    Context, CreateActionRow, CreateAllowedMentions, CreateButton, CreateInteractionResponse,
+    CreateInteractionResponseMessage, CreateMessage, EditMessage, MessageId, RoleId, UserId,
+};
 use thiserror::Error;
+use tokio::task::AbortHandle;
+use tracing::error;
 
-use crate::{err::RKBServiceRequestErr, RKBServiceRequest};
+use crate::{
+    action::{
+        pomodoro::{arm_pomodoro, Phase, Pomodoro},
+        stopwatch::Stopwatch,
+    },
+    breakdown_string,
+    err::RKBServiceRequestErr,
+    resource::Resources,
+    split_action, split_flags,
+    text::duration::{format_duration, parse_duration},
+    RKBServiceRequest,
+};
+
+pub(crate) const TIMERS_DOCUMENT: &str = "timers";
+const TIMER_MESSAGE_PREFIX: &str = "[Time: ";
+const LATE_TOLERANCE: TimeDelta = TimeDelta::seconds(30);
+const MAX_PREVIEW_LENGTH: usize = 40;
+const MAX_FIRED_TIMERS: usize = 50;
+const DEFAULT_MAX_TIMER_DURATION: Duration = Duration::from_secs(30 * 86400);
+/// Timers at least this long get a progress bar, edited about `PROGRESS_STEPS` times.
+const PROGRESS_MIN_DURATION: Duration = Duration::from_secs(600);
+const PROGRESS_MIN_INTERVAL: Duration = Duration::from_secs(60);
+const PROGRESS_STEPS: u32 = 20;
+const PROGRESS_WIDTH: i64 = 20;
+const SNOOZE_PREFIX: &str = "timer_snooze";
+const SNOOZE_MINUTES: [i64; 3] = [5, 15, 60];
 
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("user input time is required")]
-    CannotParseEmptyUserInputTime,
     #[error("user input time is out of bounds")]
     Overflow,
-    #[error("user input time does not follow standard pattern")]
-    DoesntFollowPattern,
+    #[error("timer id is not a number")]
+    InvalidTimerId(String),
+    #[error("failed to respond to snooze")]
+    SnoozeResponse,
 }
 
-#[derive(Debug, Clone, PartialEq)]
-struct Timer {
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Timer {
+    pub id: u64,
+    pub channel_id: ChannelId,
+    pub author_id: UserId,
     pub dob: DateTime<Utc>,
-    pub delta: Duration,
+    pub deadline: DateTime<Utc>,
     pub recalled_message: String,
+    pub pinned_message_id: Option<MessageId>,
+    /// Deliver to the author's direct messages instead of the channel.
+    #[serde(default)]
+    pub dm: bool,
+}
+
+/// Pending timers, pomodoros and stopwatches sharing one id sequence, persisted so they survive restarts.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct TimerStore {
+    pub(crate) next_id: u64,
+    pub(crate) timers: Vec<Timer>,
+    /// Recently fired timers that can still be snoozed, oldest first.
+    #[serde(default)]
+    pub(crate) fired: Vec<Timer>,
+    #[serde(default)]
+    pub(crate) pomodoros: Vec<Pomodoro>,
+    #[serde(default)]
+    pub(crate) stopwatches: Vec<Stopwatch>,
+    /// Channels countdowns were pinned in, checked for stale pins on startup.
+    #[serde(default)]
+    pub(crate) pin_channels: BTreeSet<ChannelId>,
+}
+
+/// Cancellation handles of armed timers, keyed by timer id.
+#[derive(Debug, Default)]
+pub struct TimerRegistry {
+    handles: Mutex<HashMap<u64, AbortHandle>>,
+}
+
+impl TimerRegistry {
+    pub fn contains(&self, id: u64) -> bool {
+        self.handles
+            .lock()
+            .expect("Timer registry lock poisoned.")
+            .contains_key(&id)
+    }
+
+    pub(crate) fn remove(&self, id: u64) -> Option<AbortHandle> {
+        self.handles
+            .lock()
+            .expect("Timer registry lock poisoned.")
+            .remove(&id)
+    }
+
+    /// Spawns a task and registers its cancellation handle before the task can deregister itself.
+    pub(crate) fn spawn<F>(&self, id: u64, task: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let mut handles = self.handles.lock().expect("Timer registry lock poisoned.");
+        handles.insert(id, tokio::spawn(task).abort_handle());
+    }
+}
+
+impl Timer {
+    pub fn delta(&self) -> Duration {
+        (self.deadline - self.dob).to_std().unwrap_or_default()
+    }
+
+    /// Pinned countdown text, with a progress bar for long timers.
+    fn countdown(&self, now: DateTime<Utc>) -> String {
+        if self.delta() < PROGRESS_MIN_DURATION {
+            return self.to_string();
+        }
+        let total = (self.deadline - self.dob).num_seconds().max(1);
+        let elapsed = (now - self.dob).num_seconds().clamp(0, total);
+        let filled = (elapsed * PROGRESS_WIDTH / total) as usize;
+        format!(
+            "{}\n{}{} {}%",
+            self,
+            "▓".repeat(filled),
+            "░".repeat(PROGRESS_WIDTH as usize - filled),
+            elapsed * 100 / total
+        )
+    }
+
+    fn preview(&self) -> String {
+        let mut preview = self
+            .recalled_message
+            .chars()
+            .take(MAX_PREVIEW_LENGTH)
+            .collect::<String>();
+        if self.recalled_message.chars().count() > MAX_PREVIEW_LENGTH {
+            preview += "…";
+        }
+        preview
+    }
 }
 
 impl Display for Timer {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut remainder = self.delta.as_secs();
-        let mut delta = String::new();
-        let days = remainder / 86400;
-        remainder %= 86400;
-        let hours = remainder / 3600;
-        remainder %= 3600;
-        let minutes = remainder / 60;
-        remainder %= 60;
-        let seconds = remainder;
-
-        if days > 0 {
-            delta += &format!("{}d", days);
-        };
-        if hours > 0 {
-            delta += &format!("{}h", hours);
-        };
-        if minutes > 0 {
-            delta += &format!("{}m", minutes);
-        };
-        if seconds > 0 {
-            delta += &format!("{}s", seconds);
-        };
-        let start = Local::now();
-        let end = Local::now() + self.delta;
-        writeln!(
+        let delta = format_duration(self.delta());
+        let start = self.dob.timestamp();
+        let end = self.deadline.timestamp();
+        write!(
             f,
-            "[Time: {delta} | Start: {start} | End: {end}]
-            \n{}
-            ",
-            self.recalled_message
-        )
+            "{TIMER_MESSAGE_PREFIX}{delta} | Start: <t:{start}:F> | End: <t:{end}:F> (<t:{end}:R>)]"
+        )?;
+        if !self.recalled_message.is_empty() {
+            write!(f, "\n{}", self.recalled_message)?;
+        }
+        Ok(())
     }
 }
 
@@ -62,66 +171,571 @@ impl TryFrom<RKBServiceRequest> for Timer {
     type Error = RKBServiceRequestErr;
 
     fn try_from(value: RKBServiceRequest) -> Result<Self, Self::Error> {
-        let mut content = value
-            .get_content()
-            .ok_or(Error::CannotParseEmptyUserInputTime)?;
-        let mut recalled_message = String::new();
-        if content.contains(' ') {
-            (content, recalled_message) = content
-                .split_once(' ')
-                .map(|(a, b)| (a, b.to_string()))
-                .ok_or(Error::DoesntFollowPattern)?;
-        }
-        let timedelta = try_deltatime(content.to_string())?;
-        if timedelta.is_zero() {
-            Err(Error::CannotParseEmptyUserInputTime)?;
-        }
-        let duration = timedelta.to_std().map_err(|_| Error::Overflow)?;
+        let (content, flags) = split_flags(value.get_content().unwrap_or_default());
+        let tokens = content.split_whitespace().collect::<Vec<&str>>();
+        let (timedelta, consumed) = parse_duration(&tokens)?;
+        let recalled_message = tokens[consumed..].join(" ");
+        let dob = Utc::now();
         Ok(Timer {
-            dob: Utc::now(),
-            delta: duration,
+            id: 0,
+            channel_id: value.msg.channel_id,
+            author_id: value.msg.author.id,
+            dob,
+            deadline: dob.checked_add_signed(timedelta).ok_or(Error::Overflow)?,
             recalled_message,
+            pinned_message_id: None,
+            dm: flags.iter().any(|v| v == "dm"),
         })
     }
 }
 
 impl RKBServiceRequest {
     pub async fn timer(&self) -> Result<(), RKBServiceRequestErr> {
-        let timer = Timer::try_from(self.clone())?;
-        let timer_message = self.try_send_message(timer.to_string()).await?;
+        let (subaction, argument) =
+            split_action(self.get_content().unwrap_or_default().to_string());
+        match subaction.as_str() {
+            "cancel" | "stop" => return self.cancel_timer(&argument).await,
+            "list" => return self.timers().await,
+            "max" => return self.max_timer_duration(argument.trim()).await,
+            _ => (),
+        }
+        let timer = match Timer::try_from(self.clone()) {
+            Ok(timer) => timer,
+            Err(RKBServiceRequestErr::Duration(err)) => {
+                self.try_send_message(format!("{}. ⏳", err)).await?;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        self.start_timer(timer).await
+    }
+
+    /// Longest timer allowed here, configured per guild.
+    pub fn max_timer(&self) -> Duration {
+        self.guild_settings()
+            .max_timer_seconds
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_MAX_TIMER_DURATION)
+    }
+
+    /// Shows the longest timer allowed, or sets it when a moderator passes a duration or `default`.
+    async fn max_timer_duration(&self, argument: &str) -> Result<(), RKBServiceRequestErr> {
+        if argument.is_empty() {
+            self.try_send_message(format!(
+                "Timers can run for up to {} here. ⏳",
+                format_duration(self.max_timer())
+            ))
+            .await?;
+            return Ok(());
+        }
+        if !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only a moderator can change the longest timer. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let max_timer_seconds = match argument {
+            "default" => None,
+            _ => {
+                let tokens = argument.split_whitespace().collect::<Vec<&str>>();
+                match parse_duration(&tokens) {
+                    Ok((delta, _)) => delta.to_std().ok().map(|v| v.as_secs()),
+                    Err(err) => {
+                        self.try_send_message(format!("{}. ⏳", err)).await?;
+                        return Ok(());
+                    }
+                }
+            }
+        };
+        self.update_guild_settings(|settings| settings.max_timer_seconds = max_timer_seconds)?;
+        self.try_send_message(format!(
+            "Timers can now run for up to {}. ⏳",
+            format_duration(self.max_timer())
+        ))
+        .await?;
+        Ok(())
+    }
+
+    /// Pins a countdown for the timer, persists it and arms it.
+    pub(crate) async fn start_timer(&self, mut timer: Timer) -> Result<(), RKBServiceRequestErr> {
+        if timer.delta() > self.max_timer() {
+            self.try_send_message(format!(
+                "Timers can run for up to {} here. ⏳",
+                format_duration(self.max_timer())
+            ))
+            .await?;
+            return Ok(());
+        }
+        let timer_message = self.try_send_message(timer.countdown(Utc::now())).await?;
         self.try_pin(timer_message.id).await?;
-        tokio::time::sleep(timer.delta).await;
-        self.try_delete_message(timer_message.id).await?;
-        self.try_send_message(timer.recalled_message).await?;
+        timer.pinned_message_id = Some(timer_message.id);
+        schedule_timer(self.ctx.clone(), self.rsc.clone(), timer)?;
+        Ok(())
+    }
+
+    /// Lists the caller's pending timers, pomodoros and stopwatches in this channel, or everyone's with `channel`.
+    pub async fn timers(&self) -> Result<(), RKBServiceRequestErr> {
+        let everyone = self.get_content().is_some_and(|v| v.contains("channel"));
+        let store = self.rsc.storage.load::<TimerStore>(TIMERS_DOCUMENT);
+        let now = Utc::now();
+        let timers = store.timers.iter().map(|v| {
+            let mut line = format!(
+                "`#{}` {} left",
+                v.id,
+                format_delta((v.deadline - now).max(TimeDelta::zero()))
+            );
+            if !v.recalled_message.is_empty() {
+                line += &format!(" · {}", v.preview());
+            }
+            (v.id, v.channel_id, v.author_id, line)
+        });
+        let pomodoros = store.pomodoros.iter().map(|v| {
+            let line = match v.phase(now) {
+                (Phase::Work(number), end) => format!(
+                    "`#{}` 🍅 work {}/{}, {} left",
+                    v.id,
+                    number,
+                    v.cycles,
+                    format_delta(end - now)
+                ),
+                (Phase::Break(number), end) => format!(
+                    "`#{}` ☕ break {}/{}, {} left",
+                    v.id,
+                    number,
+                    v.cycles - 1,
+                    format_delta(end - now)
+                ),
+                (Phase::Done, _) => format!("`#{}` 🍅 done", v.id),
+            };
+            (v.id, v.channel_id, v.author_id, line)
+        });
+        let stopwatches = store.stopwatches.iter().map(|v| {
+            let mut line = format!("`#{}` ⏱️ {} elapsed", v.id, format_delta(v.elapsed(now)));
+            if !v.label.is_empty() {
+                line += &format!(" · {}", v.label);
+            }
+            (v.id, v.channel_id, v.author_id, line)
+        });
+        let mut entries = timers
+            .chain(pomodoros)
+            .chain(stopwatches)
+            .filter(|(_, channel_id, _, _)| *channel_id == self.msg.channel_id)
+            .filter(|(_, _, author_id, _)| everyone || *author_id == self.msg.author.id)
+            .collect::<Vec<_>>();
+        entries.sort_by_key(|(id, _, _, _)| *id);
+        let lines = entries
+            .into_iter()
+            .map(|(_, _, author_id, mut line)| {
+                if everyone {
+                    let author = self
+                        .ctx
+                        .cache
+                        .user(author_id)
+                        .map(|user| user.name.clone())
+                        .unwrap_or(author_id.to_string());
+                    line += &format!(" · {}", author);
+                }
+                line
+            })
+            .collect::<Vec<String>>();
+        let response = match lines.is_empty() {
+            true => String::from("No pending timers. ⏳"),
+            false => format!(">>> {}", lines.join("\n")),
+        };
+        self.try_send_message(response).await?;
         Ok(())
     }
-}
 
-fn try_deltatime(string: String) -> Result<TimeDelta, RKBServiceRequestErr> {
-    let mut chars = string.chars();
-    let mut buffer = chars
-        .next()
-        .map(|v| v.to_string())
-        .ok_or(Error::DoesntFollowPattern)?;
-    let mut delta = TimeDelta::zero();
-    for char in chars {
-        let o_multiplier = match char {
-            'd' => Some(86400),
-            'h' => Some(3600),
-            'm' => Some(60),
-            's' => Some(1),
-            _ => None,
+    /// Aborts a pending timer and removes its countdown. Only the author or a moderator may cancel.
+    pub(crate) async fn cancel_timer(&self, argument: &str) -> Result<(), RKBServiceRequestErr> {
+        let id = argument
+            .trim()
+            .trim_start_matches('#')
+            .parse::<u64>()
+            .map_err(|_| Error::InvalidTimerId(argument.to_string()))?;
+        let store = self.rsc.storage.load::<TimerStore>(TIMERS_DOCUMENT);
+        let timer = store.timers.into_iter().find(|v| v.id == id);
+        let pomodoro = store.pomodoros.into_iter().find(|v| v.id == id);
+        let author_id = timer
+            .as_ref()
+            .map(|v| v.author_id)
+            .or(pomodoro.as_ref().map(|v| v.author_id))
+            .or(store
+                .stopwatches
+                .iter()
+                .find(|v| v.id == id)
+                .map(|v| v.author_id));
+        let Some(author_id) = author_id else {
+            self.try_send_message(format!("No pending timer `#{}`. 🎣", id))
+                .await?;
+            return Ok(());
         };
-        if o_multiplier.is_none() {
-            buffer.push(char);
+        if author_id != self.msg.author.id && !self.is_moderator() {
+            self.try_send_message(String::from(
+                "Only the timer's author or a moderator can cancel it. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        if let Some(handle) = self.rsc.timers.remove(id) {
+            handle.abort();
+        }
+        self.rsc
+            .storage
+            .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                store.timers.retain(|v| v.id != id);
+                store.pomodoros.retain(|v| v.id != id);
+                store.stopwatches.retain(|v| v.id != id);
+            })?;
+        if let Some(Pomodoro {
+            channel_id,
+            status_message_id: Some(status_message_id),
+            ..
+        }) = pomodoro
+        {
+            let builder =
+                EditMessage::new().content(format!("🍅 Pomodoro `#{}` cancelled. 🛑", id));
+            let _ = channel_id
+                .edit_message(&self.ctx.http, status_message_id, builder)
+                .await;
+        }
+        if let Some(Timer {
+            channel_id,
+            pinned_message_id: Some(pinned_message_id),
+            ..
+        }) = timer
+        {
+            let _ = channel_id.unpin(&self.ctx.http, pinned_message_id).await;
+            let _ = channel_id
+                .delete_message(&self.ctx.http, pinned_message_id)
+                .await;
+        }
+        self.try_send_message(format!("Timer `#{}` cancelled. 🛑", id))
+            .await?;
+        Ok(())
+    }
+}
+
+/// Persists a timer under the next id and arms it. Returns the id.
+fn schedule_timer(
+    ctx: Context,
+    rsc: Resources,
+    mut timer: Timer,
+) -> Result<u64, RKBServiceRequestErr> {
+    timer.id = rsc
+        .storage
+        .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+            store.next_id += 1;
+            if timer.pinned_message_id.is_some() {
+                store.pin_channels.insert(timer.channel_id);
+            }
+            store.timers.push(Timer {
+                id: store.next_id,
+                ..timer.clone()
+            });
+            store.next_id
+        })?;
+    let id = timer.id;
+    arm_timer(ctx, rsc, timer);
+    Ok(id)
+}
+
+/// Spawns the timer task and registers its cancellation handle.
+pub fn arm_timer(ctx: Context, rsc: Resources, timer: Timer) {
+    let registry = rsc.timers.clone();
+    let id = timer.id;
+    registry.spawn(id, async move {
+        if let Err(err) = run_timer(ctx, rsc.clone(), timer).await {
+            error!("Failed to run timer: {:?}", err);
+        }
+        rsc.timers.remove(id);
+    });
+}
+
+/// Sleeps until the deadline, then replaces the pinned countdown with a notification
+/// mentioning the author and anyone mentioned in the timer, with snooze buttons.
+pub async fn run_timer(
+    ctx: Context,
+    rsc: Resources,
+    timer: Timer,
+) -> Result<(), RKBServiceRequestErr> {
+    if let (Some(pinned_message_id), true) = (
+        timer.pinned_message_id,
+        timer.delta() >= PROGRESS_MIN_DURATION,
+    ) {
+        let interval = (timer.delta() / PROGRESS_STEPS).max(PROGRESS_MIN_INTERVAL);
+        while (timer.deadline - Utc::now()).to_std().unwrap_or_default() > interval {
+            tokio::time::sleep(interval).await;
+            let builder = EditMessage::new().content(timer.countdown(Utc::now()));
+            if let Err(err) = timer
+                .channel_id
+                .edit_message(&ctx.http, pinned_message_id, builder)
+                .await
+            {
+                error!("Failed to update timer countdown: {:?}", err);
+            }
+        }
+    }
+    let remaining = (timer.deadline - Utc::now()).to_std().unwrap_or_default();
+    tokio::time::sleep(remaining).await;
+    if let Some(pinned_message_id) = timer.pinned_message_id {
+        if let Err(err) = timer
+            .channel_id
+            .delete_message(&ctx.http, pinned_message_id)
+            .await
+        {
+            error!("Failed to delete timer countdown message: {:?}", err);
+        }
+    }
+    let late = Utc::now() - timer.deadline;
+    let mut response = match timer.recalled_message.is_empty() {
+        true => format!("⏰ <@{}> Time's up!", timer.author_id),
+        false => format!("⏰ <@{}> {}", timer.author_id, timer.recalled_message),
+    };
+    if timer.dm {
+        response += &format!(" · <#{}>", timer.channel_id);
+    }
+    if late > LATE_TOLERANCE {
+        response += &format!(" *(late by {})*", format_delta(late));
+    }
+    let (mut users, roles) = mentions(&timer.recalled_message);
+    users.push(timer.author_id);
+    let roles = mentionable_roles(&ctx, &timer, roles).await;
+    let allowed_mentions = CreateAllowedMentions::new().users(users).roles(roles);
+    let mut chunks = breakdown_string(response);
+    let last_chunk = chunks.pop_back().unwrap_or_default();
+    let mut builders = chunks
+        .into_iter()
+        .map(|v| CreateMessage::new().content(v))
+        .collect::<Vec<CreateMessage>>();
+    builders.push(
+        CreateMessage::new()
+            .content(last_chunk)
+            .components(vec![snooze_buttons(timer.id)]),
+    );
+    let mut direct = timer.dm;
+    for builder in builders {
+        let builder = builder.allowed_mentions(allowed_mentions.clone());
+        if direct {
+            match timer
+                .author_id
+                .direct_message(&ctx.http, builder.clone())
+                .await
+            {
+                Ok(_) => continue,
+                Err(err) => {
+                    error!(
+                        "Failed to direct message timer, posting in channel: {:?}",
+                        err
+                    );
+                    direct = false;
+                }
+            }
+        }
+        timer
+            .channel_id
+            .send_message(&ctx.http, builder)
+            .await
+            .map_err(|_| RKBServiceRequestErr::DiscordMessageSendFailure(timer.preview()))?;
+    }
+    rsc.storage
+        .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+            store.timers.retain(|v| v.id != timer.id);
+            store.fired.push(timer.clone());
+            let overflow = store.fired.len().saturating_sub(MAX_FIRED_TIMERS);
+            store.fired.drain(..overflow);
+        })?;
+    Ok(())
+}
+
+/// Users and roles mentioned in a timer message.
+fn mentions(message: &str) -> (Vec<UserId>, Vec<RoleId>) {
+    let tokens = message
+        .split('<')
+        .skip(1)
+        .filter_map(|v| v.split_once('>'))
+        .map(|(v, _)| format!("<{}>", v));
+    let (mut users, mut roles) = (Vec::new(), Vec::new());
+    for token in tokens {
+        if let Some(role_id) = parse_role_mention(&token) {
+            roles.push(role_id);
+        } else if let Some(user_id) = parse_user_mention(&token) {
+            users.push(user_id);
+        }
+    }
+    (users, roles)
+}
+
+/// Roles the author may ping, the mentionable ones or all of them with permission to mention
+/// everyone in the channel. None when the guild is not cached.
+async fn mentionable_roles(ctx: &Context, timer: &Timer, roles: Vec<RoleId>) -> Vec<RoleId> {
+    if roles.is_empty() {
+        return roles;
+    }
+    let Ok(Channel::Guild(channel)) = timer.channel_id.to_channel(ctx).await else {
+        return Vec::new();
+    };
+    let member = channel.guild_id.member(ctx, timer.author_id).await.ok();
+    let Some(guild) = ctx.cache.guild(channel.guild_id) else {
+        return Vec::new();
+    };
+    // Threads take their permissions from the channel they were started in.
+    let permission_channel = match channel.thread_metadata {
+        Some(_) => channel
+            .parent_id
+            .and_then(|v| guild.channels.get(&v))
+            .unwrap_or(&channel),
+        None => &channel,
+    };
+    let mention_everyone = member.is_some_and(|v| {
+        guild
+            .user_permissions_in(permission_channel, &v)
+            .mention_everyone()
+    });
+    roles
+        .into_iter()
+        .filter(|v| mention_everyone || guild.roles.get(v).is_some_and(|v| v.mentionable))
+        .collect()
+}
+
+fn snooze_buttons(id: u64) -> CreateActionRow {
+    CreateActionRow::Buttons(
+        SNOOZE_MINUTES
+            .iter()
+            .map(|minutes| {
+                CreateButton::new(format!("{}:{}:{}", SNOOZE_PREFIX, id, minutes))
+                    .label(format!("Snooze {}", snooze_label(*minutes)))
+                    .style(ButtonStyle::Secondary)
+                    .emoji('💤')
+            })
+            .collect(),
+    )
+}
+
+fn snooze_label(minutes: i64) -> String {
+    match minutes % 60 {
+        0 => format!("{}h", minutes / 60),
+        _ => format!("{}m", minutes),
+    }
+}
+
+/// Re-arms a fired timer when its author presses a snooze button. Other components are ignored.
+pub async fn snooze_timer(
+    ctx: Context,
+    rsc: Resources,
+    component: ComponentInteraction,
+) -> Result<(), RKBServiceRequestErr> {
+    let mut parts = component.data.custom_id.split(':');
+    let (Some(SNOOZE_PREFIX), Some(id), Some(minutes)) = (parts.next(), parts.next(), parts.next())
+    else {
+        return Ok(());
+    };
+    let (Ok(id), Ok(minutes)) = (id.parse::<u64>(), minutes.parse::<i64>()) else {
+        return Ok(());
+    };
+    let store = rsc.storage.load::<TimerStore>(TIMERS_DOCUMENT);
+    let fired = store.fired.into_iter().find(|v| v.id == id);
+    let response = match fired {
+        Some(timer) if timer.author_id == component.user.id => {
+            rsc.storage
+                .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                    store.fired.retain(|v| v.id != id)
+                })?;
+            let dob = Utc::now();
+            let snoozed = Timer {
+                dob,
+                deadline: dob + TimeDelta::minutes(minutes),
+                pinned_message_id: None,
+                ..timer
+            };
+            let snoozed_id = schedule_timer(ctx.clone(), rsc, snoozed)?;
+            CreateInteractionResponse::UpdateMessage(
+                CreateInteractionResponseMessage::new()
+                    .content(format!(
+                        "{}\n💤 Snoozed for {} as `#{}`.",
+                        component.message.content,
+                        snooze_label(minutes),
+                        snoozed_id
+                    ))
+                    .components(Vec::new()),
+            )
+        }
+        Some(_) => CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("Only the timer's author can snooze it. 🔒")
+                .ephemeral(true),
+        ),
+        None => CreateInteractionResponse::Message(
+            CreateInteractionResponseMessage::new()
+                .content("This timer can no longer be snoozed. ⌛")
+                .ephemeral(true),
+        ),
+    };
+    component
+        .create_response(&ctx.http, response)
+        .await
+        .map_err(|_| Error::SnoozeResponse)?;
+    Ok(())
+}
+
+/// Re-arms persisted timers after a restart, firing overdue ones and clearing stale countdown pins.
+pub async fn rearm_timers(ctx: Context, rsc: Resources) {
+    if rsc.timers_rearmed.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    let store = rsc.storage.load::<TimerStore>(TIMERS_DOCUMENT);
+    // Every channel that held a countdown, including ones whose timers are all gone.
+    let mut channel_ids = store.pin_channels.clone();
+    channel_ids.extend(store.timers.iter().map(|v| v.channel_id));
+    let bot_user_id = ctx.cache.current_user().id;
+    for channel_id in channel_ids {
+        let Ok(pins) = channel_id.pins(&ctx.http).await else {
             continue;
+        };
+        let stale_pins = pins.into_iter().filter(|pin| {
+            pin.author.id == bot_user_id
+                && pin.content.starts_with(TIMER_MESSAGE_PREFIX)
+                && !store
+                    .timers
+                    .iter()
+                    .any(|v| v.pinned_message_id == Some(pin.id))
+        });
+        for pin in stale_pins {
+            if let Err(err) = channel_id.delete_message(&ctx.http, pin.id).await {
+                error!("Failed to clean up stale timer pin: {:?}", err);
+            }
         }
-        let multiplier = o_multiplier.unwrap();
-        let base = buffer.parse::<i64>().map_err(|_| Error::Overflow)?;
-        let delta_additional = &TimeDelta::try_seconds(base * multiplier).ok_or(Error::Overflow)?;
-        delta = TimeDelta::checked_add(&delta, delta_additional).ok_or(Error::Overflow)?;
-        buffer.clear();
     }
+    let pruned = rsc
+        .storage
+        .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+            let TimerStore {
+                timers,
+                pin_channels,
+                ..
+            } = store;
+            pin_channels.retain(|channel_id| timers.iter().any(|v| v.channel_id == *channel_id));
+        });
+    if let Err(err) = pruned {
+        error!("Failed to prune timer pin channels: {:?}", err);
+    }
+    for timer in store.timers {
+        if !rsc.timers.contains(timer.id) {
+            arm_timer(ctx.clone(), rsc.clone(), timer);
+        }
+    }
+    for pomodoro in store.pomodoros {
+        if !rsc.timers.contains(pomodoro.id) {
+            arm_pomodoro(ctx.clone(), rsc.clone(), pomodoro);
+        }
+    }
+}
 
-    Ok(delta)
+pub(crate) fn format_delta(delta: TimeDelta) -> String {
+    match (delta.num_hours(), delta.num_minutes(), delta.num_seconds()) {
+        (hours, minutes, _) if hours > 0 => format!("{}h{}m", hours, minutes % 60),
+        (_, minutes, seconds) if minutes > 0 => format!("{}m{}s", minutes, seconds % 60),
+        (_, _, seconds) => format!("{}s", seconds),
+    }
 }
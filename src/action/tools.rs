@@ -0,0 +1,147 @@
+use std::sync::{Arc, Mutex};
+
+use serde_json::{json, Value};
+
+use crate::{
+    err::RKBServiceRequestErr,
+    llm::{ToolCall, ToolDefinition},
+    RKBServiceRequest, ENTRY_STRING,
+};
+
+const WEATHER: &str = "weather";
+const FORECAST: &str = "forecast";
+const TIMER: &str = "timer";
+const GEO: &str = "geo";
+
+/// Replies of an action run as a chat tool, only posted to the channel when `visible`.
+#[derive(Debug, Default)]
+pub struct ToolOutput {
+    pub(crate) replies: Mutex<Vec<String>>,
+    /// Set for actions whose replies the channel needs, like a timer's pinned countdown.
+    pub(crate) visible: bool,
+}
+
+/// Bot actions chat may call, described for the model.
+pub fn chat_tools() -> Vec<ToolDefinition> {
+    vec![
+        ToolDefinition {
+            name: WEATHER,
+            description: "Current weather at a place.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "location": {"type": "string", "description": "Place name or lat,lon."},
+                    "detail": {"type": "boolean", "description": "Add comfort details."},
+                },
+                "required": ["location"],
+            }),
+        },
+        ToolDefinition {
+            name: FORECAST,
+            description: "5 day weather forecast for a place.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "location": {"type": "string", "description": "Place name or lat,lon."},
+                },
+                "required": ["location"],
+            }),
+        },
+        ToolDefinition {
+            name: TIMER,
+            description: "Start a timer that mentions the user with a message when it ends.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "duration": {"type": "string", "description": "Like 20m, 1h30m or 2 days."},
+                    "message": {"type": "string", "description": "What to remind about."},
+                },
+                "required": ["duration"],
+            }),
+        },
+        ToolDefinition {
+            name: GEO,
+            description: "Look up a place: coordinates, timezone and local time.",
+            parameters: json!({
+                "type": "object",
+                "properties": {
+                    "query": {"type": "string", "description": "Place name or lat,lon."},
+                },
+                "required": ["query"],
+            }),
+        },
+    ]
+}
+
+/// Short label of a call for the list of tools used.
+pub fn tool_label(call: &ToolCall) -> String {
+    let argument = ["location", "duration", "query"]
+        .iter()
+        .find_map(|key| call.arguments.get(key).and_then(Value::as_str));
+    match argument {
+        Some(argument) => format!("{} {}", call.name, argument),
+        None => call.name.clone(),
+    }
+}
+
+impl RKBServiceRequest {
+    /// Runs `call` through the action it names, as if the invoking user had sent the command,
+    /// and returns what the action replied. Only timers are posted, lookups go to the model alone.
+    pub(crate) async fn run_tool(&self, call: &ToolCall) -> String {
+        let argument = |key: &str| {
+            call.arguments
+                .get(key)
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .trim()
+                .to_string()
+        };
+        let output = Arc::new(ToolOutput {
+            visible: call.name == TIMER,
+            ..ToolOutput::default()
+        });
+        let mut request = self.clone();
+        request.tool_output = Some(output.clone());
+        let command = |command: String| format!("{}{}", ENTRY_STRING, command.trim());
+        let result: Result<(), RKBServiceRequestErr> = match call.name.as_str() {
+            WEATHER => {
+                let detail = match call.arguments.get("detail").and_then(Value::as_bool) {
+                    Some(true) => " --detail",
+                    _ => "",
+                };
+                request.msg.content =
+                    command(format!("weather {}{}", argument("location"), detail));
+                request.weather().await
+            }
+            FORECAST => {
+                request.msg.content = command(format!("forecast {}", argument("location")));
+                request.forecast().await
+            }
+            TIMER => {
+                request.msg.content = command(format!(
+                    "timer {} {}",
+                    argument("duration"),
+                    argument("message")
+                ));
+                request.timer().await
+            }
+            GEO => {
+                request.msg.content = command(format!("geo {}", argument("query")));
+                request.geo().await
+            }
+            _ => return format!("There is no tool named `{}`.", call.name),
+        };
+        let mut text = output
+            .replies
+            .lock()
+            .expect("Tool output lock poisoned.")
+            .join("\n");
+        if let Err(err) = result {
+            text += &format!("\nFailed: {}", err);
+        }
+        match text.trim().is_empty() {
+            true => String::from("Done."),
+            false => text.trim().to_string(),
+        }
+    }
+}
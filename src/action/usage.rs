@@ -0,0 +1,399 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use crate::{
+    err::RKBServiceRequestErr,
+    llm::{estimate_tokens, ChatMessage, ChatResponse, LlmProvider, DEEPSEEK, OPENAI},
+    split_action, RKBServiceRequest,
+};
+
+const LLM_USAGE_DOCUMENT: &str = "llm_usage";
+/// Usage of direct messages is kept under this key instead of a guild id.
+const DIRECT_MESSAGES: &str = "direct";
+const KEPT_DAYS: usize = 62;
+const KEPT_MONTHS: usize = 24;
+/// USD per million prompt and completion tokens, by provider and model prefix. The first
+/// matching prefix wins, unknown models and local Ollama ones count as free.
+const PRICES: [(&str, &str, f64, f64); 10] = [
+    (DEEPSEEK, "deepseek-chat", 0.28, 0.42),
+    (DEEPSEEK, "deepseek-reasoner", 0.28, 0.42),
+    (OPENAI, "gpt-4o-mini", 0.15, 0.6),
+    (OPENAI, "gpt-4o", 2.5, 10.0),
+    (OPENAI, "gpt-4.1-mini", 0.4, 1.6),
+    (OPENAI, "gpt-4.1-nano", 0.1, 0.4),
+    (OPENAI, "gpt-4.1", 2.0, 8.0),
+    (OPENAI, "o4-mini", 1.1, 4.4),
+    (OPENAI, "o3-mini", 1.1, 4.4),
+    (OPENAI, "o3", 2.0, 8.0),
+];
+
+/// Tokens and estimated cost of chat completions.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TokenUsage {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    /// Estimated USD.
+    pub cost: f64,
+}
+
+impl TokenUsage {
+    pub fn total_tokens(&self) -> u64 {
+        self.prompt_tokens + self.completion_tokens
+    }
+
+    fn add(&mut self, other: &TokenUsage) {
+        self.requests += other.requests;
+        self.prompt_tokens += other.prompt_tokens;
+        self.completion_tokens += other.completion_tokens;
+        self.cost += other.cost;
+    }
+}
+
+/// Usage of a guild in a day or month, with its channels and users by id.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+struct GuildUsage {
+    total: TokenUsage,
+    channels: HashMap<String, TokenUsage>,
+    users: HashMap<String, TokenUsage>,
+}
+
+/// Guild usage keyed by guild id, under days (`YYYY-MM-DD`) and months (`YYYY-MM`) in UTC.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct UsageStore {
+    days: BTreeMap<String, HashMap<String, GuildUsage>>,
+    months: BTreeMap<String, HashMap<String, GuildUsage>>,
+}
+
+impl UsageStore {
+    /// Adds `usage` under the day and month of `now` for the guild, channel and user `keys`,
+    /// dropping the oldest periods past what is kept.
+    fn add(&mut self, now: DateTime<Utc>, keys: &(String, String, String), usage: &TokenUsage) {
+        let (guild_id, channel_id, user_id) = keys;
+        for (periods, period, kept) in [
+            (
+                &mut self.days,
+                now.format("%Y-%m-%d").to_string(),
+                KEPT_DAYS,
+            ),
+            (
+                &mut self.months,
+                now.format("%Y-%m").to_string(),
+                KEPT_MONTHS,
+            ),
+        ] {
+            let guild = periods
+                .entry(period)
+                .or_default()
+                .entry(guild_id.clone())
+                .or_default();
+            guild.total.add(usage);
+            guild
+                .channels
+                .entry(channel_id.clone())
+                .or_default()
+                .add(usage);
+            guild.users.entry(user_id.clone()).or_default().add(usage);
+            while periods.len() > kept {
+                periods.pop_first();
+            }
+        }
+    }
+}
+
+impl RKBServiceRequest {
+    /// Stats for the author, channel and guild, or `quota` to show or set daily token quotas.
+    pub async fn usage(&self) -> Result<(), RKBServiceRequestErr> {
+        let (subaction, argument) =
+            split_action(self.get_content().unwrap_or_default().to_string());
+        match subaction.as_str() {
+            "quota" | "quotas" => self.token_quota(argument.trim()).await,
+            _ => {
+                self.try_send_message(self.usage_stats()).await?;
+                Ok(())
+            }
+        }
+    }
+
+    /// Adds a completion to the author's, channel's and guild's usage. Replies without usage
+    /// from the provider are counted by estimate so they still count against quotas.
+    pub(crate) fn record_usage(
+        &self,
+        provider: &dyn LlmProvider,
+        messages: &[ChatMessage],
+        response: &ChatResponse,
+    ) {
+        let (prompt_tokens, completion_tokens) = match response.usage {
+            Some(usage) => (usage.prompt_tokens, usage.completion_tokens),
+            None => (
+                messages.iter().map(|v| v.estimate_tokens() as u64).sum(),
+                estimate_tokens(&response.content) as u64
+                    + estimate_tokens(response.reasoning.as_deref().unwrap_or_default()) as u64,
+            ),
+        };
+        let usage = TokenUsage {
+            requests: 1,
+            prompt_tokens,
+            completion_tokens,
+            cost: estimate_cost(
+                provider.name(),
+                provider.model(),
+                prompt_tokens,
+                completion_tokens,
+            ),
+        };
+        let keys = self.usage_keys();
+        let now = Utc::now();
+        let updated = self
+            .rsc
+            .storage
+            .update(LLM_USAGE_DOCUMENT, |store: &mut UsageStore| {
+                store.add(now, &keys, &usage)
+            });
+        if let Err(err) = updated {
+            error!("Failed to record chat usage: {:?}", err);
+        }
+    }
+
+    /// Why the author can't chat right now, when a daily token quota is used up.
+    pub(crate) fn quota_exceeded(&self) -> Option<String> {
+        let settings = self.guild_settings();
+        let (user_quota, guild_quota) =
+            (settings.user_daily_token_quota, settings.daily_token_quota);
+        if user_quota.is_none() && guild_quota.is_none() {
+            return None;
+        }
+        let (guild_id, _, user_id) = self.usage_keys();
+        let today = self
+            .period_usage(false)
+            .remove(&guild_id)
+            .unwrap_or_default();
+        let user_used = today
+            .users
+            .get(&user_id)
+            .map_or(0, TokenUsage::total_tokens);
+        if let Some(quota) = user_quota.filter(|v| user_used >= *v) {
+            return Some(format!(
+                "You used your {} chat tokens for today here, they renew at midnight UTC. 🧮",
+                quota
+            ));
+        }
+        if let Some(quota) = guild_quota.filter(|v| today.total.total_tokens() >= *v) {
+            return Some(format!(
+                "This server used its {} chat tokens for today, they renew at midnight UTC. 🧮",
+                quota
+            ));
+        }
+        None
+    }
+
+    fn usage_keys(&self) -> (String, String, String) {
+        (
+            self.msg
+                .guild_id
+                .map_or(DIRECT_MESSAGES.to_string(), |v| v.to_string()),
+            self.parent_channel()
+                .unwrap_or(self.msg.channel_id)
+                .to_string(),
+            self.msg.author.id.to_string(),
+        )
+    }
+
+    /// Usage by guild of today, or of this month.
+    fn period_usage(&self, month: bool) -> HashMap<String, GuildUsage> {
+        let mut store = self.rsc.storage.load::<UsageStore>(LLM_USAGE_DOCUMENT);
+        let now = Utc::now();
+        match month {
+            true => store.months.remove(&now.format("%Y-%m").to_string()),
+            false => store.days.remove(&now.format("%Y-%m-%d").to_string()),
+        }
+        .unwrap_or_default()
+    }
+
+    fn usage_stats(&self) -> String {
+        let (guild_id, channel_id, user_id) = self.usage_keys();
+        let lines = [("Today", false), ("This month", true)].map(|(label, month)| {
+            let guild = self
+                .period_usage(month)
+                .remove(&guild_id)
+                .unwrap_or_default();
+            let user = guild.users.get(&user_id).copied().unwrap_or_default();
+            let channel = guild.channels.get(&channel_id).copied().unwrap_or_default();
+            let scope = match self.msg.guild_id {
+                Some(_) => format!(
+                    " · this channel {} · server {}",
+                    format_usage(&channel),
+                    format_usage(&guild.total)
+                ),
+                None => String::new(),
+            };
+            format!("{}: you {}{}", label, format_usage(&user), scope)
+        });
+        let settings = self.guild_settings();
+        let quota = |quota: Option<u64>| quota.map_or(String::from("none"), |v| v.to_string());
+        format!(
+            "🧮 Chat usage, estimated at list prices\n{}\n{}\nDaily token quotas: {} per user · {} for the server",
+            lines[0],
+            lines[1],
+            quota(settings.user_daily_token_quota),
+            quota(settings.daily_token_quota)
+        )
+    }
+
+    /// `user|server [TOKENS|off]`, shows the quotas without a value.
+    async fn token_quota(&self, argument: &str) -> Result<(), RKBServiceRequestErr> {
+        let mut words = argument.split_whitespace();
+        let (scope, value) = (words.next().unwrap_or_default(), words.next());
+        let user = match scope {
+            "user" => true,
+            "server" | "guild" => false,
+            _ => {
+                self.try_send_message(String::from("Use `quota user|server [TOKENS|off]`. 🧮"))
+                    .await?;
+                return Ok(());
+            }
+        };
+        let Some(value) = value else {
+            self.try_send_message(self.usage_stats()).await?;
+            return Ok(());
+        };
+        if !self.is_admin() {
+            self.try_send_message(String::from(
+                "Only a server admin can change token quotas. 🔒",
+            ))
+            .await?;
+            return Ok(());
+        }
+        let quota = match value {
+            "off" | "none" => None,
+            _ => match value.replace('_', "").parse::<u64>() {
+                Ok(quota) if quota > 0 => Some(quota),
+                _ => {
+                    self.try_send_message(String::from(
+                        "Quotas are a number of tokens per day, or `off`. 🧮",
+                    ))
+                    .await?;
+                    return Ok(());
+                }
+            },
+        };
+        let updated = self.update_guild_settings(|settings| match user {
+            true => settings.user_daily_token_quota = quota,
+            false => settings.daily_token_quota = quota,
+        });
+        if let Err(RKBServiceRequestErr::Settings(err)) = updated {
+            self.try_send_message(format!("{}. 🧮", err)).await?;
+            return Ok(());
+        }
+        updated?;
+        let scope = match user {
+            true => "per user",
+            false => "for the server",
+        };
+        let reply = match quota {
+            Some(quota) => format!("Chat is limited to {} tokens a day {}. 🧮", quota, scope),
+            None => format!("Chat has no daily token quota {} now. 🧮", scope),
+        };
+        self.try_send_message(reply).await?;
+        Ok(())
+    }
+}
+
+fn estimate_cost(provider: &str, model: &str, prompt_tokens: u64, completion_tokens: u64) -> f64 {
+    PRICES
+        .iter()
+        .find(|(name, prefix, _, _)| *name == provider && model.starts_with(prefix))
+        .map_or(0.0, |(_, _, prompt, completion)| {
+            (prompt_tokens as f64 * prompt + completion_tokens as f64 * completion) / 1_000_000.0
+        })
+}
+
+fn format_usage(usage: &TokenUsage) -> String {
+    format!(
+        "{} tokens in {} requests (~${:.4})",
+        usage.total_tokens(),
+        usage.requests,
+        usage.cost
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Days, TimeZone};
+
+    use super::*;
+    use crate::llm::OLLAMA;
+
+    /// Cost of a million prompt and a million completion tokens.
+    fn cost(provider: &str, model: &str) -> f64 {
+        estimate_cost(provider, model, 1_000_000, 1_000_000)
+    }
+
+    #[test]
+    fn longest_prefixes_come_first() {
+        assert_eq!(cost(OPENAI, "o3-mini"), 1.1 + 4.4);
+        assert_eq!(cost(OPENAI, "o3-mini-2025-01-31"), 1.1 + 4.4);
+        assert_eq!(cost(OPENAI, "o3"), 2.0 + 8.0);
+        assert_eq!(cost(OPENAI, "gpt-4o-mini"), 0.15 + 0.6);
+        assert_eq!(cost(OPENAI, "gpt-4o-2024-08-06"), 2.5 + 10.0);
+        assert_eq!(cost(OPENAI, "gpt-4.1-nano"), 0.1 + 0.4);
+        assert_eq!(cost(OPENAI, "gpt-4.1"), 2.0 + 8.0);
+        assert_eq!(estimate_cost(DEEPSEEK, "deepseek-chat", 500_000, 0), 0.14);
+    }
+
+    #[test]
+    fn every_prefix_is_reachable() {
+        for (i, (provider, prefix, _, _)) in PRICES.iter().enumerate() {
+            let shadowed = PRICES[..i]
+                .iter()
+                .any(|(name, earlier, _, _)| name == provider && prefix.starts_with(earlier));
+            assert!(!shadowed, "{} is priced by an earlier prefix", prefix);
+        }
+    }
+
+    #[test]
+    fn unknown_models_are_free() {
+        assert_eq!(cost(OPENAI, "gpt-9"), 0.0);
+        assert_eq!(cost(OLLAMA, "gpt-4o"), 0.0);
+        assert_eq!(cost(DEEPSEEK, "o3"), 0.0);
+    }
+
+    #[test]
+    fn keeps_recent_days_and_months() {
+        let keys = (
+            String::from("guild"),
+            String::from("channel"),
+            String::from("user"),
+        );
+        let usage = TokenUsage {
+            requests: 1,
+            prompt_tokens: 10,
+            completion_tokens: 5,
+            cost: 0.5,
+        };
+        let start = Utc.with_ymd_and_hms(2024, 1, 1, 12, 0, 0).unwrap();
+        let mut store = UsageStore::default();
+        for day in 0..1000 {
+            store.add(start + Days::new(day), &keys, &usage);
+        }
+        // 1000 days from 2024-01-01 reach 2026-09-26.
+        assert_eq!(store.days.len(), KEPT_DAYS);
+        assert_eq!(store.days.keys().last().unwrap(), "2026-09-26");
+        assert_eq!(
+            store.days.keys().next().unwrap(),
+            &(start + Days::new(1000 - KEPT_DAYS as u64))
+                .format("%Y-%m-%d")
+                .to_string()
+        );
+        assert_eq!(store.months.len(), KEPT_MONTHS);
+        assert_eq!(store.months.keys().next().unwrap(), "2024-10");
+        assert_eq!(store.months.keys().last().unwrap(), "2026-09");
+        let september = &store.months["2026-09"]["guild"];
+        assert_eq!(september.total.requests, 26);
+        assert_eq!(september.users["user"].total_tokens(), 26 * 15);
+        assert_eq!(september.channels["channel"], september.total);
+    }
+}
@@ -1,280 +1,329 @@
 use std::fmt::Display;
 
-use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
-use crate::{err::RKBServiceRequestErr, token::TokenType, RKBServiceRequest};
+use crate::{
+    comfort::ComfortIndices,
+    err::RKBServiceRequestErr,
+    split_flags,
+    weather::{CurrentWeather, Forecast, Location},
+    RKBServiceRequest,
+};
 
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("placeholder")]
     Placeholder,
-    #[error("failed to query for openweather")]
-    OpenWeatherQueryError,
-    #[error("failed to parse openweather")]
-    OpenWeatherParseError,
 }
 
-#[derive(Default, Debug, Clone, Serialize, Deserialize)]
-pub struct GeoJson {
-    pub zip: String,
-    pub name: String,
-    pub lat: f64,
-    pub lon: f64,
-    pub country: String,
-}
+const DEFAULT_ZIP_CODE: &str = "91776";
+const COMPARISON_SEPARATOR: &str = " vs ";
+const MAX_COMPARED_LOCATIONS: usize = 5;
+const MAX_CONDITIONS_WIDTH: usize = 16;
 
-impl Display for GeoJson {
+impl Display for Location {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(zip) = &self.zip {
+            write!(f, "{}, ", zip)?;
+        }
         write!(
             f,
-            "{}, {}, {} ({}, {})",
-            self.zip, self.name, self.country, self.lat, self.lon
+            "{}, {} ({}, {})",
+            self.name, self.country, self.lat, self.lon
         )
     }
 }
 
-/// From openweathermap-0.2.4
-/// Location coordinates
-
-#[derive(Deserialize, Debug)]
-
-pub struct Coord {
-    /// geo location, longitude
-    pub _lon: f64,
-
-    /// geo location, latitude
-    pub _lat: f64,
-}
-
-/// Weather condition description
-
-#[derive(Deserialize, Debug)]
-
-pub struct Weather {
-    /// Weather condition id
-    pub _id: u64,
-
-    /// Group of weather parameters (Rain, Snow, Extreme etc.)
-    pub _main: String,
-
-    /// Weather condition
-    pub description: String,
-
-    /// Weather icon id
-    pub _icon: String,
-}
-
-/// Detailed weather report
-
-#[derive(Deserialize, Debug)]
-
-pub struct Main {
-    /// Temperature. Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
-    pub _temp: f64,
-
-    /// Temperature. This temperature parameter accounts for the human perception of weather.
-    /// Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
-    pub feels_like: f64,
-
-    /// Atmospheric pressure (on the sea level, if there is no sea_level or grnd_level data), hPa
-    pub _pressure: f64,
-
-    /// Humidity, %
-    pub humidity: f64,
-
-    /// Minimum temperature at the moment.
-    /// This is minimal currently observed temperature (within large megalopolises and urban areas).
-    /// Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
-    pub temp_min: f64,
-
-    /// Maximum temperature at the moment.
-    /// This is maximal currently observed temperature (within large megalopolises and urban areas).
-    /// Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
-    pub temp_max: f64,
-
-    /// Atmospheric pressure on the sea level, hPa
-    pub _sea_level: Option<f64>,
-
-    /// Atmospheric pressure on the ground level, hPa
-    pub _grnd_level: Option<f64>,
-}
-
-/// Detailed wind report
-
-#[derive(Deserialize, Debug)]
-
-pub struct Wind {
-    /// Wind speed. Unit Default: meter/sec, Metric: meter/sec, Imperial: miles/hour.
-    pub speed: f64,
-
-    /// Wind direction, degrees (meteorological)
-    pub _deg: f64,
-
-    /// Wind gust. Unit Default: meter/sec, Metric: meter/sec, Imperial: miles/hour
-    pub _gust: Option<f64>,
+impl Display for CurrentWeather {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            ">>> ■ {}\n{}\n{}% humidity\n{} > {} > {}{}\n{} {} winds",
+            self.name,
+            self.description,
+            self.humidity,
+            self.temp_max.round(),
+            self.feels_like.round(),
+            self.temp_min.round(),
+            self.units.temperature_symbol(),
+            (self.wind_speed * 10.0).round() / 10.0,
+            self.units.speed_symbol(),
+        )
+    }
 }
 
-/// Detailed clouds report
-
-#[derive(Deserialize, Debug)]
-
-pub struct Clouds {
-    /// Cloudiness, %
-    pub _all: f64,
+impl Display for Forecast {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, ">>> ■ {}", self.name)?;
+        for day in &self.days {
+            write!(
+                f,
+                "\n{} {} > {}{} {}",
+                day.date.format("%a %m/%d"),
+                day.temp_max.round(),
+                day.temp_min.round(),
+                self.units.temperature_symbol(),
+                day.description
+            )?;
+            if let Some(precipitation) = day.precipitation {
+                write!(f, " ({}% precip.)", precipitation.round())?;
+            }
+        }
+        Ok(())
+    }
 }
 
-/// Rain or snow volume report
-
-#[derive(Deserialize, Debug)]
-
-pub struct Volume {
-    /// Volume for the last 1 hour, mm
-    #[serde(rename = "1h")]
-    pub _h1: Option<f64>,
-
-    /// Volume for the last 3 hours, mm
-    #[serde(rename = "3h")]
-    pub _h3: Option<f64>,
+/// Current weather of several locations, rendered side by side.
+#[derive(Debug, Default)]
+struct WeatherComparison {
+    reports: Vec<(Location, CurrentWeather)>,
+    failures: Vec<String>,
 }
 
-/// Additional information
-
-#[derive(Deserialize, Debug)]
-
-pub struct Sys {
-    /// Internal parameter
-    #[serde(rename = "type")]
-    pub _type_: Option<u64>,
-
-    /// Internal parameter
-    pub _id: Option<u64>,
-
-    /// Internal parameter
-    pub _message: Option<f64>,
-
-    /// Country code (GB, JP etc.)
-    pub _country: String,
-
-    /// Sunrise time, unix, UTC
-    pub _sunrise: i64,
-
-    /// Sunset time, unix, UTC
-    pub _sunset: i64,
+impl WeatherComparison {
+    fn extreme(&self, warmest: bool) -> Option<&(Location, CurrentWeather)> {
+        let ordering = |a: &&(Location, CurrentWeather), b: &&(Location, CurrentWeather)| {
+            let a_temp = a.1.units.to_celsius(a.1.temp);
+            let b_temp = b.1.units.to_celsius(b.1.temp);
+            a_temp.total_cmp(&b_temp)
+        };
+        match warmest {
+            true => self.reports.iter().max_by(ordering),
+            false => self.reports.iter().min_by(ordering),
+        }
+    }
 }
 
-#[derive(Deserialize, Debug)]
-
-/// current weather report in a nested struct
-pub struct WeatherJson {
-    /// report origin coordinates
-    pub _coord: Coord,
-
-    /// vector with one item of weather condition descriptions
-    pub weather: Vec<Weather>,
-
-    /// Internal parameter
-    pub _base: String,
-
-    /// detailed weather report
-    pub main: Main,
-
-    /// Visibility, meter
-    pub _visibility: u64,
-
-    /// detailed wind report
-    pub wind: Wind,
-
-    /// detailed clouds report
-    pub _clouds: Clouds,
-
-    /// detailed rain report
-    pub _rain: Option<Volume>,
-
-    /// detailed snow report
-    pub _snow: Option<Volume>,
-
-    /// Time of data calculation, unix, UTC
-    pub _dt: i64,
-
-    /// additional information
-    pub _sys: Sys,
-
-    /// Shift in seconds from UTC
-    pub _timezone: i64,
-
-    /// City ID
-    pub _id: u64,
-
-    /// City name
-    pub name: String,
-
-    /// Internal parameter
-    pub _cod: u64,
+impl Display for WeatherComparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let warmest = self.extreme(true).filter(|_| self.reports.len() > 1);
+        let coldest = self.extreme(false).filter(|_| self.reports.len() > 1);
+        let mut rows = vec![
+            vec![String::new()],
+            vec![String::from("Temp")],
+            vec![String::from("Feels")],
+            vec![String::from("Sky")],
+            vec![String::from("Humidity")],
+            vec![String::from("Wind")],
+        ];
+        for report in &self.reports {
+            let (location, current) = report;
+            let symbol = current.units.temperature_symbol();
+            let marker = match (warmest, coldest) {
+                (Some(v), _) if std::ptr::eq(v, report) => " ▲",
+                (_, Some(v)) if std::ptr::eq(v, report) => " ▼",
+                _ => "",
+            };
+            let column = [
+                location.name.clone(),
+                format!("{}{}{}", current.temp.round(), symbol, marker),
+                format!("{}{}", current.feels_like.round(), symbol),
+                current
+                    .description
+                    .chars()
+                    .take(MAX_CONDITIONS_WIDTH)
+                    .collect(),
+                format!("{}%", current.humidity),
+                format!(
+                    "{} {}",
+                    (current.wind_speed * 10.0).round() / 10.0,
+                    current.units.speed_symbol()
+                ),
+            ];
+            for (row, cell) in rows.iter_mut().zip(column) {
+                row.push(cell);
+            }
+        }
+        let widths = (0..=self.reports.len())
+            .map(|i| {
+                rows.iter()
+                    .filter_map(|row| row.get(i))
+                    .map(|cell| cell.chars().count())
+                    .max()
+                    .unwrap_or_default()
+                    + 2
+            })
+            .collect::<Vec<usize>>();
+        writeln!(f, "```")?;
+        for row in &rows {
+            let line = row
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{:<width$}", cell, width = width))
+                .collect::<String>();
+            writeln!(f, "{}", line.trim_end())?;
+        }
+        write!(f, "```")?;
+        if let (Some((warm, warm_current)), Some((cold, cold_current))) = (warmest, coldest) {
+            write!(
+                f,
+                "\n🔥 Warmest: {} ({}{})\n🧊 Coldest: {} ({}{})",
+                warm.name,
+                warm_current.temp.round(),
+                warm_current.units.temperature_symbol(),
+                cold.name,
+                cold_current.temp.round(),
+                cold_current.units.temperature_symbol(),
+            )?;
+        }
+        for failure in &self.failures {
+            write!(f, "\nCould not find weather for {}.", failure)?;
+        }
+        Ok(())
+    }
 }
 
-impl Display for WeatherJson {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let weather = self.weather.first().expect("Weather can not be displayed.");
-        write!(
-            f,
-            ">>> ■ {}\n{}\n{}% humidity\n{} > {} > {}°F\n{} mph winds",
-            self.name,
-            weather.description,
-            self.main.humidity,
-            self.main.temp_max.round(),
-            self.main.feels_like.round(),
-            self.main.temp_min.round(),
-            (self.wind.speed * 10.0).round() / 10.0
-        )
+impl CurrentWeather {
+    pub fn comfort(&self) -> ComfortIndices {
+        ComfortIndices::new(self.temp, self.humidity, self.wind_speed, self.units)
     }
 }
 
 impl RKBServiceRequest {
-    pub async fn geo(self) -> Result<(), RKBServiceRequestErr> {
-        let response = self.clone().geo_reqwest().await?;
-        self.try_send_message(response.to_string()).await?;
+    pub async fn weather(self) -> Result<(), RKBServiceRequestErr> {
+        let (location, flags) = split_flags(self.get_content().unwrap_or_default());
+        let fresh = flags.iter().any(|v| v == "fresh");
+        if location.contains(COMPARISON_SEPARATOR) {
+            let locations = location
+                .split(COMPARISON_SEPARATOR)
+                .map(str::trim)
+                .collect();
+            return self.weather_comparison(locations, fresh).await;
+        }
+        let geo = self.geocode(&location, fresh).await?;
+        let response = self.weather_provider(fresh).current(&geo).await?;
+        let mut report = response.to_string();
+        if flags.iter().any(|v| v == "detail") {
+            report += &format!("\n{}", response.comfort());
+        }
+        self.try_send_message(report).await?;
         Ok(())
     }
 
-    pub async fn weather(self) -> Result<(), RKBServiceRequestErr> {
-        let geo = self.clone().geo_reqwest().await?;
-        let api_key = self.tkn.get(&TokenType::OpenWeather)?;
-        let url = format!(
-            "https://api.openweathermap.org/data/2.5/weather?lat={}&lon={}&appid={}&units=imperial",
-            geo.lat, geo.lon, api_key
-        );
-        let response = reqwest::get(url)
-            .await
-            .unwrap_or_else(|_| {
-                panic!(
-                    "Failed to query api for openweather. ({}, {})",
-                    geo.lat, geo.lon
-                )
+    /// Fetches every location concurrently through the same geocode and current weather path.
+    async fn weather_comparison(
+        &self,
+        locations: Vec<&str>,
+        fresh: bool,
+    ) -> Result<(), RKBServiceRequestErr> {
+        let locations = &locations[..locations.len().min(MAX_COMPARED_LOCATIONS)];
+        let handles = locations
+            .iter()
+            .map(|location| {
+                let rkb = self.clone();
+                let location = location.to_string();
+                tokio::spawn(async move {
+                    let geo = rkb.geocode(&location, fresh).await?;
+                    let current = rkb.weather_provider(fresh).current(&geo).await?;
+                    Ok::<_, RKBServiceRequestErr>((geo, current))
+                })
             })
-            .json::<WeatherJson>()
-            .await
-            .expect("Failed to parse api package as json.");
+            .collect::<Vec<_>>();
+        let mut comparison = WeatherComparison::default();
+        for (location, handle) in locations.iter().zip(handles) {
+            match handle.await {
+                Ok(Ok(report)) => comparison.reports.push(report),
+                _ => comparison.failures.push(location.to_string()),
+            }
+        }
+        self.try_send_message(comparison.to_string()).await?;
+        Ok(())
+    }
+
+    pub async fn forecast(self) -> Result<(), RKBServiceRequestErr> {
+        let (location, flags) = split_flags(self.get_content().unwrap_or_default());
+        let fresh = flags.iter().any(|v| v == "fresh");
+        let geo = self.geocode(&location, fresh).await?;
+        let response = self.weather_provider(fresh).forecast(&geo).await?;
         self.try_send_message(response.to_string()).await?;
         Ok(())
     }
 
-    async fn geo_reqwest(self) -> Result<GeoJson, RKBServiceRequestErr> {
-        let zip_code = "91776";
-        let country_code = "US";
-        let api_key = self.tkn.get(&TokenType::OpenWeather)?;
-        let url = format!(
-            "http://api.openweathermap.org/geo/1.0/zip?zip={},{}&appid={}",
-            zip_code, country_code, api_key
-        );
+    pub async fn weather_cache(self) -> Result<(), RKBServiceRequestErr> {
+        self.try_send_message(self.rsc.weather_cache.to_string())
+            .await?;
+        Ok(())
+    }
+
+    /// Geocodes a user supplied location, defaulting to the home zip code when empty.
+    pub(crate) async fn geocode(
+        &self,
+        location: &str,
+        fresh: bool,
+    ) -> Result<Location, RKBServiceRequestErr> {
+        let query = match location.trim() {
+            "" => DEFAULT_ZIP_CODE,
+            query => query,
+        };
+        self.weather_provider(fresh).geocode(query).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comfort::Units;
+
+    fn report(
+        name: &str,
+        description: &str,
+        temp: f64,
+        units: Units,
+    ) -> (Location, CurrentWeather) {
+        let location = Location {
+            name: name.to_string(),
+            zip: None,
+            country: String::new(),
+            lat: 0.0,
+            lon: 0.0,
+        };
+        let current = CurrentWeather {
+            name: name.to_string(),
+            description: description.to_string(),
+            temp,
+            feels_like: temp - 2.0,
+            temp_min: temp,
+            temp_max: temp,
+            humidity: 40.0,
+            wind_speed: 3.25,
+            wind_deg: 0.0,
+            units,
+        };
+        (location, current)
+    }
+
+    #[test]
+    fn renders_rows_side_by_side() {
+        // 50°F is colder than 30°C, despite the larger number.
+        let comparison = WeatherComparison {
+            reports: vec![
+                report("Anchorage", "light rain", 50.0, Units::Imperial),
+                report("Lisbon", "clear sky with some haze", 30.0, Units::Metric),
+            ],
+            failures: vec![String::from("Atlantis")],
+        };
+        let expected = "```
+          Anchorage   Lisbon
+Temp      50°F ▼      30°C ▲
+Feels     48°F        28°C
+Sky       light rain  clear sky with s
+Humidity  40%         40%
+Wind      3.3 mph     3.3 m/s
+```
+🔥 Warmest: Lisbon (30°C)
+🧊 Coldest: Anchorage (50°F)
+Could not find weather for Atlantis.";
+        assert_eq!(comparison.to_string(), expected);
+    }
 
-        let query_response = reqwest::get(url)
-            .await
-            .map_err(|_| Error::OpenWeatherQueryError)?;
-        let geojson = query_response
-            .json::<GeoJson>()
-            .await
-            .map_err(|_| Error::OpenWeatherParseError)?;
-        Ok(geojson)
+    #[test]
+    fn single_report_has_no_extremes() {
+        let comparison = WeatherComparison {
+            reports: vec![report("Lisbon", "clear sky", 30.0, Units::Metric)],
+            failures: Vec::new(),
+        };
+        let rendered = comparison.to_string();
+        assert!(rendered.contains("Temp      30°C\n"), "{}", rendered);
+        assert!(!rendered.contains("Warmest"));
     }
 }
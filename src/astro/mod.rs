@@ -0,0 +1,34 @@
+pub mod moon;
+pub mod sun;
+
+use chrono::{DateTime, Utc};
+
+const UNIX_EPOCH_JD: f64 = 2440587.5;
+const J2000_JD: f64 = 2451545.0;
+const SECONDS_PER_DAY: f64 = 86400.0;
+
+pub fn julian_day(datetime: DateTime<Utc>) -> f64 {
+    datetime.timestamp() as f64 / SECONDS_PER_DAY + UNIX_EPOCH_JD
+}
+
+pub fn from_julian_day(julian_day: f64) -> DateTime<Utc> {
+    let seconds = ((julian_day - UNIX_EPOCH_JD) * SECONDS_PER_DAY).round() as i64;
+    DateTime::from_timestamp(seconds, 0).unwrap_or_default()
+}
+
+/// Julian centuries since J2000.0.
+fn julian_century(julian_day: f64) -> f64 {
+    (julian_day - J2000_JD) / 36525.0
+}
+
+fn sin_deg(degrees: f64) -> f64 {
+    degrees.to_radians().sin()
+}
+
+fn cos_deg(degrees: f64) -> f64 {
+    degrees.to_radians().cos()
+}
+
+fn normalize_degrees(degrees: f64) -> f64 {
+    degrees.rem_euclid(360.0)
+}
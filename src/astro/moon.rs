@@ -0,0 +1,222 @@
+use std::fmt::Display;
+
+use chrono::{DateTime, Utc};
+
+use super::{cos_deg, from_julian_day, julian_century, julian_day, normalize_degrees, sin_deg};
+
+pub const SYNODIC_MONTH: f64 = 29.530588861;
+const LUNATION_ZERO_JD: f64 = 2451550.09766;
+/// Difference between terrestrial and universal time, days.
+const DELTA_T: f64 = 69.2 / 86400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MoonPhase {
+    New,
+    WaxingCrescent,
+    FirstQuarter,
+    WaxingGibbous,
+    Full,
+    WaningGibbous,
+    LastQuarter,
+    WaningCrescent,
+}
+
+impl MoonPhase {
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            MoonPhase::New => "🌑",
+            MoonPhase::WaxingCrescent => "🌒",
+            MoonPhase::FirstQuarter => "🌓",
+            MoonPhase::WaxingGibbous => "🌔",
+            MoonPhase::Full => "🌕",
+            MoonPhase::WaningGibbous => "🌖",
+            MoonPhase::LastQuarter => "🌗",
+            MoonPhase::WaningCrescent => "🌘",
+        }
+    }
+}
+
+impl Display for MoonPhase {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            MoonPhase::New => "New Moon",
+            MoonPhase::WaxingCrescent => "Waxing Crescent",
+            MoonPhase::FirstQuarter => "First Quarter",
+            MoonPhase::WaxingGibbous => "Waxing Gibbous",
+            MoonPhase::Full => "Full Moon",
+            MoonPhase::WaningGibbous => "Waning Gibbous",
+            MoonPhase::LastQuarter => "Last Quarter",
+            MoonPhase::WaningCrescent => "Waning Crescent",
+        };
+        write!(f, "{} {}", self.emoji(), name)
+    }
+}
+
+/// Moon as seen from earth at an instant, from Meeus' Astronomical Algorithms ch. 48.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Moon {
+    /// Position in the lunation, degrees. 0 is new, 180 is full.
+    pub elongation: f64,
+    /// Illuminated fraction of the disk, 0 to 1.
+    pub illumination: f64,
+}
+
+impl Moon {
+    pub fn at(datetime: DateTime<Utc>) -> Self {
+        let t = julian_century(julian_day(datetime));
+        let elongation = normalize_degrees(297.8501921 + 445267.1114034 * t);
+        let sun_anomaly = normalize_degrees(357.5291092 + 35999.0502909 * t);
+        let moon_anomaly = normalize_degrees(134.9633964 + 477198.8675055 * t);
+        let phase_angle = 180.0 - elongation - 6.289 * sin_deg(moon_anomaly)
+            + 2.100 * sin_deg(sun_anomaly)
+            - 1.274 * sin_deg(2.0 * elongation - moon_anomaly)
+            - 0.658 * sin_deg(2.0 * elongation)
+            - 0.214 * sin_deg(2.0 * moon_anomaly)
+            - 0.110 * sin_deg(elongation);
+        Moon {
+            elongation: normalize_degrees(180.0 - phase_angle),
+            illumination: (1.0 + cos_deg(phase_angle)) / 2.0,
+        }
+    }
+
+    pub fn phase(&self) -> MoonPhase {
+        match ((self.elongation + 22.5) / 45.0) as u8 % 8 {
+            0 => MoonPhase::New,
+            1 => MoonPhase::WaxingCrescent,
+            2 => MoonPhase::FirstQuarter,
+            3 => MoonPhase::WaxingGibbous,
+            4 => MoonPhase::Full,
+            5 => MoonPhase::WaningGibbous,
+            6 => MoonPhase::LastQuarter,
+            _ => MoonPhase::WaningCrescent,
+        }
+    }
+
+    /// Days since the last new moon.
+    pub fn age(&self) -> f64 {
+        self.elongation / 360.0 * SYNODIC_MONTH
+    }
+}
+
+pub fn next_new_moon(after: DateTime<Utc>) -> DateTime<Utc> {
+    next_phase(after, 0.0)
+}
+
+pub fn next_full_moon(after: DateTime<Utc>) -> DateTime<Utc> {
+    next_phase(after, 0.5)
+}
+
+/// First new (`offset` 0) or full (`offset` 0.5) moon after an instant.
+fn next_phase(after: DateTime<Utc>, offset: f64) -> DateTime<Utc> {
+    let after_jd = julian_day(after);
+    let mut lunation = ((after_jd - LUNATION_ZERO_JD) / SYNODIC_MONTH).floor() - 1.0;
+    loop {
+        let phase_jd = phase_julian_day(lunation + offset);
+        if phase_jd > after_jd {
+            return from_julian_day(phase_jd);
+        }
+        lunation += 1.0;
+    }
+}
+
+/// Meeus' Astronomical Algorithms ch. 49, principal periodic terms only.
+fn phase_julian_day(k: f64) -> f64 {
+    let t = k / 1236.85;
+    let mean = LUNATION_ZERO_JD + SYNODIC_MONTH * k + 0.00015437 * t.powi(2);
+    let e = 1.0 - 0.002516 * t - 0.0000074 * t.powi(2);
+    let sun_anomaly = 2.5534 + 29.10535670 * k - 0.0000014 * t.powi(2);
+    let moon_anomaly = 201.5643 + 385.81693528 * k + 0.0107582 * t.powi(2);
+    let latitude = 160.7108 + 390.67050284 * k - 0.0016118 * t.powi(2);
+    let node = 124.7746 - 1.56375588 * k + 0.0020672 * t.powi(2);
+    let (anomaly_term, sun_term, double_anomaly_term, latitude_term) = match k.fract() == 0.0 {
+        true => (-0.40720, 0.17241, 0.01608, 0.01039),
+        false => (-0.40614, 0.17302, 0.01614, 0.01043),
+    };
+    let correction = anomaly_term * sin_deg(moon_anomaly)
+        + sun_term * e * sin_deg(sun_anomaly)
+        + double_anomaly_term * sin_deg(2.0 * moon_anomaly)
+        + latitude_term * sin_deg(2.0 * latitude)
+        + 0.00739 * e * sin_deg(moon_anomaly - sun_anomaly)
+        - 0.00514 * e * sin_deg(moon_anomaly + sun_anomaly)
+        + 0.00208 * e.powi(2) * sin_deg(2.0 * sun_anomaly)
+        - 0.00111 * sin_deg(moon_anomaly - 2.0 * latitude)
+        - 0.00057 * sin_deg(moon_anomaly + 2.0 * latitude)
+        + 0.00056 * e * sin_deg(2.0 * moon_anomaly + sun_anomaly)
+        - 0.00042 * sin_deg(3.0 * moon_anomaly)
+        + 0.00042 * e * sin_deg(sun_anomaly + 2.0 * latitude)
+        + 0.00038 * e * sin_deg(sun_anomaly - 2.0 * latitude)
+        - 0.00024 * e * sin_deg(2.0 * moon_anomaly - sun_anomaly)
+        - 0.00017 * sin_deg(node);
+    mean + correction - DELTA_T
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Only the principal periodic terms are used, so phases land within a few minutes.
+    const TOLERANCE_SECONDS: i64 = 10 * 60;
+
+    fn assert_near(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        let error = (actual - expected).num_seconds().abs();
+        assert!(
+            error <= TOLERANCE_SECONDS,
+            "{} is {}s from {}",
+            actual,
+            error,
+            expected
+        );
+    }
+
+    #[test]
+    fn next_new_moon_matches_almanac() {
+        // New moons of 2024-01-11 11:57 and 2024-04-08 18:21 UTC (USNO).
+        let after = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        assert_near(
+            next_new_moon(after),
+            Utc.with_ymd_and_hms(2024, 1, 11, 11, 57, 0).unwrap(),
+        );
+        let after = Utc.with_ymd_and_hms(2024, 3, 25, 0, 0, 0).unwrap();
+        assert_near(
+            next_new_moon(after),
+            Utc.with_ymd_and_hms(2024, 4, 8, 18, 21, 0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn next_full_moon_matches_almanac() {
+        // Full moons of 2024-01-25 17:54 and 2024-04-23 23:49 UTC (USNO).
+        let after = Utc.with_ymd_and_hms(2024, 1, 12, 0, 0, 0).unwrap();
+        assert_near(
+            next_full_moon(after),
+            Utc.with_ymd_and_hms(2024, 1, 25, 17, 54, 0).unwrap(),
+        );
+        let after = Utc.with_ymd_and_hms(2024, 4, 9, 0, 0, 0).unwrap();
+        assert_near(
+            next_full_moon(after),
+            Utc.with_ymd_and_hms(2024, 4, 23, 23, 49, 0).unwrap(),
+        );
+    }
+
+    #[test]
+    fn illumination_and_phase() {
+        let new = Moon::at(Utc.with_ymd_and_hms(2024, 1, 11, 11, 57, 0).unwrap());
+        assert!(new.illumination < 0.01, "{}", new.illumination);
+        assert_eq!(new.phase(), MoonPhase::New);
+        // First quarter of 2024-01-18 03:53 UTC (USNO).
+        let quarter = Moon::at(Utc.with_ymd_and_hms(2024, 1, 18, 3, 53, 0).unwrap());
+        assert!(
+            (quarter.illumination - 0.5).abs() < 0.02,
+            "{}",
+            quarter.illumination
+        );
+        assert_eq!(quarter.phase(), MoonPhase::FirstQuarter);
+        let full = Moon::at(Utc.with_ymd_and_hms(2024, 1, 25, 17, 54, 0).unwrap());
+        assert!(full.illumination > 0.99, "{}", full.illumination);
+        assert_eq!(full.phase(), MoonPhase::Full);
+        let waning = Moon::at(Utc.with_ymd_and_hms(2024, 1, 29, 0, 0, 0).unwrap());
+        assert_eq!(waning.phase(), MoonPhase::WaningGibbous);
+    }
+}
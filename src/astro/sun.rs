@@ -0,0 +1,166 @@
+use chrono::{DateTime, NaiveDate, TimeDelta, Utc};
+
+use super::{cos_deg, from_julian_day, normalize_degrees, sin_deg, J2000_JD, UNIX_EPOCH_JD};
+
+const OBLIQUITY: f64 = 23.4397;
+
+/// Sun altitude below the horizon that marks each event, degrees.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Horizon {
+    /// Upper limb touching the horizon, refraction included.
+    Sunrise,
+    Civil,
+    Nautical,
+    Astronomical,
+}
+
+impl Horizon {
+    pub fn altitude(&self) -> f64 {
+        match self {
+            Horizon::Sunrise => -0.833,
+            Horizon::Civil => -6.0,
+            Horizon::Nautical => -12.0,
+            Horizon::Astronomical => -18.0,
+        }
+    }
+}
+
+/// When the sun crosses a horizon on a given day.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Crossing {
+    Times {
+        rise: DateTime<Utc>,
+        set: DateTime<Utc>,
+    },
+    /// Polar day, the sun never drops below the horizon.
+    AlwaysAbove,
+    /// Polar night, the sun never climbs above the horizon.
+    AlwaysBelow,
+}
+
+/// Sun events of a single calendar day at a location, computed with the NOAA sunrise equation.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolarDay {
+    pub date: NaiveDate,
+    pub noon: DateTime<Utc>,
+    pub sunrise: Crossing,
+    pub civil: Crossing,
+    pub nautical: Crossing,
+    pub astronomical: Crossing,
+}
+
+impl SolarDay {
+    /// `lon` is east positive.
+    pub fn new(date: NaiveDate, lat: f64, lon: f64) -> Self {
+        let days_since_epoch = (date - NaiveDate::default()).num_days() as f64;
+        let noon_jd = UNIX_EPOCH_JD + 0.5 + days_since_epoch;
+        let mean_noon = noon_jd - J2000_JD - lon / 360.0;
+        let anomaly = normalize_degrees(357.5291 + 0.98560028 * mean_noon);
+        let center = 1.9148 * sin_deg(anomaly)
+            + 0.0200 * sin_deg(2.0 * anomaly)
+            + 0.0003 * sin_deg(3.0 * anomaly);
+        let ecliptic_longitude = normalize_degrees(anomaly + center + 180.0 + 102.9372);
+        let transit = J2000_JD + mean_noon + 0.0053 * sin_deg(anomaly)
+            - 0.0069 * sin_deg(2.0 * ecliptic_longitude);
+        let declination = (sin_deg(ecliptic_longitude) * sin_deg(OBLIQUITY))
+            .asin()
+            .to_degrees();
+
+        let crossing = |horizon: Horizon| {
+            let cos_hour_angle = (sin_deg(horizon.altitude())
+                - sin_deg(lat) * sin_deg(declination))
+                / (cos_deg(lat) * cos_deg(declination));
+            if cos_hour_angle > 1.0 {
+                return Crossing::AlwaysBelow;
+            }
+            if cos_hour_angle < -1.0 {
+                return Crossing::AlwaysAbove;
+            }
+            let hour_angle = cos_hour_angle.acos().to_degrees();
+            Crossing::Times {
+                rise: from_julian_day(transit - hour_angle / 360.0),
+                set: from_julian_day(transit + hour_angle / 360.0),
+            }
+        };
+
+        SolarDay {
+            date,
+            noon: from_julian_day(transit),
+            sunrise: crossing(Horizon::Sunrise),
+            civil: crossing(Horizon::Civil),
+            nautical: crossing(Horizon::Nautical),
+            astronomical: crossing(Horizon::Astronomical),
+        }
+    }
+
+    pub fn day_length(&self) -> TimeDelta {
+        match self.sunrise {
+            Crossing::Times { rise, set } => set - rise,
+            Crossing::AlwaysAbove => TimeDelta::days(1),
+            Crossing::AlwaysBelow => TimeDelta::zero(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+
+    use super::*;
+
+    /// Published almanac times are rounded to the minute.
+    const TOLERANCE_SECONDS: i64 = 120;
+
+    fn assert_near(actual: DateTime<Utc>, expected: DateTime<Utc>) {
+        let error = (actual - expected).num_seconds().abs();
+        assert!(
+            error <= TOLERANCE_SECONDS,
+            "{} is {}s from {}",
+            actual,
+            error,
+            expected
+        );
+    }
+
+    #[test]
+    fn london_summer_solstice() {
+        // Sunrise 04:43 BST, sunset 21:21 BST (timeanddate.com).
+        let day = SolarDay::new(
+            NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(),
+            51.5074,
+            -0.1278,
+        );
+        let Crossing::Times { rise, set } = day.sunrise else {
+            panic!("London has a sunrise and sunset in June");
+        };
+        assert_near(rise, Utc.with_ymd_and_hms(2024, 6, 21, 3, 43, 0).unwrap());
+        assert_near(set, Utc.with_ymd_and_hms(2024, 6, 21, 20, 21, 0).unwrap());
+    }
+
+    #[test]
+    fn london_winter_solstice() {
+        // Sunrise 08:04 GMT, sunset 15:53 GMT (timeanddate.com).
+        let day = SolarDay::new(
+            NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(),
+            51.5074,
+            -0.1278,
+        );
+        let Crossing::Times { rise, set } = day.sunrise else {
+            panic!("London has a sunrise and sunset in December");
+        };
+        assert_near(rise, Utc.with_ymd_and_hms(2024, 12, 21, 8, 4, 0).unwrap());
+        assert_near(set, Utc.with_ymd_and_hms(2024, 12, 21, 15, 53, 0).unwrap());
+    }
+
+    #[test]
+    fn polar_day_and_night() {
+        let (lat, lon) = (69.6496, 18.956);
+        let summer = SolarDay::new(NaiveDate::from_ymd_opt(2024, 6, 21).unwrap(), lat, lon);
+        assert_eq!(summer.sunrise, Crossing::AlwaysAbove);
+        assert_eq!(summer.day_length(), TimeDelta::days(1));
+        let winter = SolarDay::new(NaiveDate::from_ymd_opt(2024, 12, 21).unwrap(), lat, lon);
+        assert_eq!(winter.sunrise, Crossing::AlwaysBelow);
+        assert!(matches!(winter.civil, Crossing::Times { .. }));
+        assert_eq!(winter.day_length(), TimeDelta::zero());
+    }
+}
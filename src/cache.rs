@@ -0,0 +1,130 @@
+use std::{
+    collections::HashMap,
+    hash::Hash,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+    time::{Duration, Instant},
+};
+
+/// In-process cache whose entries expire a fixed time after insertion.
+#[derive(Debug)]
+pub struct TtlCache<K, V> {
+    ttl: Duration,
+    entries: Mutex<HashMap<K, (Instant, V)>>,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct CacheStats {
+    pub entries: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl<K: Eq + Hash, V: Clone> TtlCache<K, V> {
+    pub fn new(ttl: Duration) -> Self {
+        TtlCache {
+            ttl,
+            entries: Mutex::new(HashMap::new()),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<V> {
+        let entries = self.entries.lock().expect("Cache lock poisoned.");
+        let value = entries
+            .get(key)
+            .filter(|(inserted, _)| inserted.elapsed() < self.ttl)
+            .map(|(_, value)| value.clone());
+        let counter = match value.is_some() {
+            true => &self.hits,
+            false => &self.misses,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        value
+    }
+
+    pub fn insert(&self, key: K, value: V) {
+        let mut entries = self.entries.lock().expect("Cache lock poisoned.");
+        entries.retain(|_, (inserted, _)| inserted.elapsed() < self.ttl);
+        entries.insert(key, (Instant::now(), value));
+    }
+
+    pub fn remove(&self, key: &K) {
+        let mut entries = self.entries.lock().expect("Cache lock poisoned.");
+        entries.remove(key);
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        CacheStats {
+            entries: self.entries.lock().expect("Cache lock poisoned.").len(),
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread::sleep;
+
+    use super::*;
+
+    const TTL: Duration = Duration::from_millis(50);
+
+    #[test]
+    fn counts_hits_and_misses() {
+        let cache = TtlCache::new(TTL);
+        assert_eq!(cache.get(&1), None);
+        cache.insert(1, "one");
+        assert_eq!(cache.get(&1), Some("one"));
+        assert_eq!(cache.get(&1), Some("one"));
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                entries: 1,
+                hits: 2,
+                misses: 1
+            }
+        );
+    }
+
+    #[test]
+    fn entries_expire() {
+        let cache = TtlCache::new(TTL);
+        cache.insert(1, "one");
+        sleep(TTL * 2);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.stats().misses, 1);
+        // Reinserting starts a new lifetime.
+        cache.insert(1, "uno");
+        assert_eq!(cache.get(&1), Some("uno"));
+    }
+
+    #[test]
+    fn insert_prunes_expired_entries() {
+        let cache = TtlCache::new(TTL);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        sleep(TTL * 2);
+        // Expired entries are kept until the next insert.
+        assert_eq!(cache.stats().entries, 2);
+        cache.insert(3, "three");
+        assert_eq!(cache.stats().entries, 1);
+    }
+
+    #[test]
+    fn remove_forgets_an_entry() {
+        let cache = TtlCache::new(TTL);
+        cache.insert(1, "one");
+        cache.insert(2, "two");
+        cache.remove(&1);
+        cache.remove(&3);
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.get(&2), Some("two"));
+    }
+}
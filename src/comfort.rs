@@ -0,0 +1,267 @@
+use std::fmt::Display;
+
+/// Unit systems accepted by OpenWeather's `units` parameter.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Units {
+    /// Kelvin, meter/sec.
+    Standard,
+    /// Celsius, meter/sec.
+    Metric,
+    /// Fahrenheit, miles/hour.
+    #[default]
+    Imperial,
+}
+
+impl Units {
+    pub fn to_celsius(self, temperature: f64) -> f64 {
+        match self {
+            Units::Standard => temperature - 273.15,
+            Units::Metric => temperature,
+            Units::Imperial => (temperature - 32.0) * 5.0 / 9.0,
+        }
+    }
+
+    pub fn convert_celsius(self, celsius: f64) -> f64 {
+        match self {
+            Units::Standard => celsius + 273.15,
+            Units::Metric => celsius,
+            Units::Imperial => celsius * 9.0 / 5.0 + 32.0,
+        }
+    }
+
+    pub fn to_kmh(self, speed: f64) -> f64 {
+        match self {
+            Units::Standard | Units::Metric => speed * 3.6,
+            Units::Imperial => speed * 1.609344,
+        }
+    }
+
+    pub fn temperature_symbol(self) -> &'static str {
+        match self {
+            Units::Standard => "K",
+            Units::Metric => "°C",
+            Units::Imperial => "°F",
+        }
+    }
+
+    pub fn speed_symbol(self) -> &'static str {
+        match self {
+            Units::Standard | Units::Metric => "m/s",
+            Units::Imperial => "mph",
+        }
+    }
+}
+
+/// Dew point from the Magnus formula, Celsius.
+pub fn dew_point(celsius: f64, humidity: f64) -> f64 {
+    let (b, c) = (17.625, 243.04);
+    let gamma = (humidity.max(1.0) / 100.0).ln() + b * celsius / (c + celsius);
+    c * gamma / (b - gamma)
+}
+
+/// NWS heat index from the Rothfusz regression, Celsius.
+pub fn heat_index(celsius: f64, humidity: f64) -> f64 {
+    let t = Units::Imperial.convert_celsius(celsius);
+    let rh = humidity;
+    let simple = 0.5 * (t + 61.0 + (t - 68.0) * 1.2 + rh * 0.094);
+    if (simple + t) / 2.0 < 80.0 {
+        return Units::Imperial.to_celsius(simple);
+    }
+    let mut index = -42.379 + 2.04901523 * t + 10.14333127 * rh
+        - 0.22475541 * t * rh
+        - 0.00683783 * t * t
+        - 0.05481717 * rh * rh
+        + 0.00122874 * t * t * rh
+        + 0.00085282 * t * rh * rh
+        - 0.00000199 * t * t * rh * rh;
+    if rh < 13.0 && (80.0..=112.0).contains(&t) {
+        index -= (13.0 - rh) / 4.0 * ((17.0 - (t - 95.0).abs()) / 17.0).sqrt();
+    }
+    if rh > 85.0 && (80.0..=87.0).contains(&t) {
+        index += (rh - 85.0) / 10.0 * (87.0 - t) / 5.0;
+    }
+    Units::Imperial.to_celsius(index)
+}
+
+/// North American wind chill index, Celsius. Only defined at or below 10°C with wind above 4.8 km/h.
+pub fn wind_chill(celsius: f64, kmh: f64) -> Option<f64> {
+    if celsius > 10.0 || kmh <= 4.8 {
+        return None;
+    }
+    let v = kmh.powf(0.16);
+    Some(13.12 + 0.6215 * celsius - 11.37 * v + 0.3965 * celsius * v)
+}
+
+/// Canadian humidex, on a Celsius-like scale.
+pub fn humidex(celsius: f64, humidity: f64) -> f64 {
+    let dew_point_kelvin = dew_point(celsius, humidity) + 273.15;
+    let vapor_pressure = 6.11 * (5417.7530 * (1.0 / 273.16 - 1.0 / dew_point_kelvin)).exp();
+    celsius + 0.5555 * (vapor_pressure - 10.0)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Comfort {
+    Frigid,
+    Cold,
+    Cool,
+    Comfortable,
+    Warm,
+    Humid,
+    Oppressive,
+    Dangerous,
+}
+
+/// Rough comfort rating from the apparent temperature and dew point, both Celsius.
+pub fn comfort(apparent: f64, dew_point: f64) -> Comfort {
+    match apparent {
+        v if v >= 41.0 => Comfort::Dangerous,
+        v if v >= 32.0 => Comfort::Oppressive,
+        _ if dew_point >= 18.0 => Comfort::Humid,
+        v if v >= 27.0 => Comfort::Warm,
+        v if v >= 18.0 => Comfort::Comfortable,
+        v if v >= 10.0 => Comfort::Cool,
+        v if v >= -10.0 => Comfort::Cold,
+        _ => Comfort::Frigid,
+    }
+}
+
+impl Display for Comfort {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let text = match self {
+            Comfort::Frigid => "🥶 Frigid",
+            Comfort::Cold => "🧣 Cold",
+            Comfort::Cool => "🧥 Cool",
+            Comfort::Comfortable => "😊 Comfortable",
+            Comfort::Warm => "😎 Warm",
+            Comfort::Humid => "💦 Humid",
+            Comfort::Oppressive => "🥵 Oppressive",
+            Comfort::Dangerous => "⚠️ Dangerous heat",
+        };
+        write!(f, "{}", text)
+    }
+}
+
+/// Derived comfort indices, reported back in the unit system they were measured in.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ComfortIndices {
+    pub units: Units,
+    pub dew_point: f64,
+    pub heat_index: f64,
+    pub wind_chill: Option<f64>,
+    pub humidex: f64,
+    pub comfort: Comfort,
+}
+
+impl ComfortIndices {
+    pub fn new(temperature: f64, humidity: f64, wind_speed: f64, units: Units) -> Self {
+        let celsius = units.to_celsius(temperature);
+        let kmh = units.to_kmh(wind_speed);
+        let dew_point = dew_point(celsius, humidity);
+        let heat_index = heat_index(celsius, humidity);
+        let wind_chill = wind_chill(celsius, kmh);
+        let apparent = match wind_chill {
+            Some(wind_chill) => wind_chill,
+            None if celsius >= 27.0 => heat_index,
+            None => celsius,
+        };
+        ComfortIndices {
+            units,
+            dew_point: units.convert_celsius(dew_point),
+            heat_index: units.convert_celsius(heat_index),
+            wind_chill: wind_chill.map(|v| units.convert_celsius(v)),
+            humidex: humidex(celsius, humidity),
+            comfort: comfort(apparent, dew_point),
+        }
+    }
+}
+
+impl Display for ComfortIndices {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbol = self.units.temperature_symbol();
+        write!(
+            f,
+            "{}\nDew point {}{}\nHeat index {}{}",
+            self.comfort,
+            self.dew_point.round(),
+            symbol,
+            self.heat_index.round(),
+            symbol
+        )?;
+        if let Some(wind_chill) = self.wind_chill {
+            write!(f, "\nWind chill {}{}", wind_chill.round(), symbol)?;
+        }
+        write!(f, "\nHumidex {}", self.humidex.round())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_near(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn dew_point_reference_values() {
+        assert_near(dew_point(25.0, 60.0), 16.7, 0.1);
+        assert_near(dew_point(20.0, 50.0), 9.3, 0.1);
+        assert_near(dew_point(10.0, 100.0), 10.0, 0.01);
+    }
+
+    #[test]
+    fn heat_index_matches_nws_table() {
+        // 90°F at 70% is 106°F, 96°F at 65% is 121°F.
+        let to_celsius = |v| Units::Imperial.to_celsius(v);
+        assert_near(heat_index(to_celsius(90.0), 70.0), to_celsius(106.0), 0.6);
+        assert_near(heat_index(to_celsius(96.0), 65.0), to_celsius(121.0), 0.6);
+        // Below 80°F the simple formula is close to the air temperature.
+        assert_near(heat_index(20.0, 50.0), 20.0, 1.0);
+    }
+
+    #[test]
+    fn wind_chill_matches_environment_canada_table() {
+        assert_near(wind_chill(-10.0, 20.0).unwrap(), -17.9, 0.1);
+        assert_near(wind_chill(0.0, 10.0).unwrap(), -3.3, 0.1);
+        assert_eq!(wind_chill(10.5, 30.0), None);
+        assert_eq!(wind_chill(-5.0, 4.8), None);
+    }
+
+    #[test]
+    fn humidex_matches_environment_canada_table() {
+        assert_near(humidex(30.0, 70.0), 41.0, 1.0);
+        assert_near(humidex(25.0, 40.0), 27.0, 1.0);
+    }
+
+    #[test]
+    fn indices_in_metric() {
+        let indices = ComfortIndices::new(30.0, 70.0, 3.0, Units::Metric);
+        assert_eq!(indices.wind_chill, None);
+        assert_near(indices.heat_index, 35.0, 1.0);
+        assert_near(indices.humidex, 41.0, 1.0);
+        assert_eq!(indices.comfort, Comfort::Oppressive);
+        // 1 m/s is under the wind chill's 4.8 km/h.
+        let calm = ComfortIndices::new(5.0, 50.0, 1.0, Units::Metric);
+        assert_eq!(calm.wind_chill, None);
+        assert_eq!(calm.comfort, Comfort::Cold);
+    }
+
+    #[test]
+    fn indices_in_imperial() {
+        // -10°C with a 20 km/h wind.
+        let indices = ComfortIndices::new(14.0, 50.0, 20.0 / 1.609344, Units::Imperial);
+        let wind_chill = indices.wind_chill.unwrap();
+        assert_near(wind_chill, Units::Imperial.convert_celsius(-17.9), 0.2);
+        assert_eq!(indices.comfort, Comfort::Frigid);
+        // 51°F is above the wind chill's 10°C.
+        let mild = ComfortIndices::new(51.0, 50.0, 20.0, Units::Imperial);
+        assert_eq!(mild.wind_chill, None);
+        assert_eq!(mild.comfort, Comfort::Cool);
+    }
+}
@@ -6,12 +6,32 @@ pub enum RKBServiceRequestErr {
     Unknown,
     #[error("token keys error")]
     Token(#[from] crate::token::Error),
+    #[error("storage error")]
+    Storage(#[from] crate::storage::Error),
+    #[error("guild settings error")]
+    Settings(#[from] crate::settings::Error),
+    #[error("duration error")]
+    Duration(#[from] crate::text::duration::Error),
     #[error("timer action error")]
     Timer(#[from] crate::action::timer::Error),
-    #[error("deepseek action error")]
-    Deepseek(#[from] crate::action::deepseek::Error),
+    #[error("remind action error")]
+    Remind(#[from] crate::action::remind::Error),
+    #[error("chat action error")]
+    Chat(#[from] crate::action::chat::Error),
+    #[error("llm provider error")]
+    Llm(#[from] crate::llm::Error),
+    #[error("persona action error")]
+    Persona(#[from] crate::action::persona::Error),
     #[error("weather action error")]
     Weather(#[from] crate::action::weather::Error),
+    #[error("weather provider error")]
+    WeatherProvider(#[from] crate::weather::Error),
+    #[error("geo action error")]
+    Geo(#[from] crate::action::geo::Error),
+    #[error("air quality action error")]
+    Aqi(#[from] crate::action::aqi::Error),
+    #[error("almanac action error")]
+    Almanac(#[from] crate::action::almanac::Error),
     #[error("failed to send discord message")]
     DiscordMessageSendFailure(String),
     #[error("attempted to send no messages")]
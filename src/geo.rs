@@ -0,0 +1,125 @@
+use std::sync::LazyLock;
+
+use chrono_tz::Tz;
+use tzf_rs::DefaultFinder;
+
+static TIMEZONE_FINDER: LazyLock<DefaultFinder> = LazyLock::new(DefaultFinder::new);
+
+/// IANA timezone of a coordinate, looked up in the bundled timezone boundary dataset.
+pub fn timezone(lat: f64, lon: f64) -> Tz {
+    TIMEZONE_FINDER
+        .get_tz_name(lon, lat)
+        .parse::<Tz>()
+        .unwrap_or(Tz::UTC)
+}
+
+const EARTH_RADIUS_KM: f64 = 6371.0088;
+const KM_PER_MILE: f64 = 1.609344;
+const COMPASS_POINTS: [&str; 16] = [
+    "N", "NNE", "NE", "ENE", "E", "ESE", "SE", "SSE", "S", "SSW", "SW", "WSW", "W", "WNW", "NW",
+    "NNW",
+];
+
+/// Parses `lat,lon` in decimal degrees.
+pub fn parse_coordinates(text: &str) -> Option<(f64, f64)> {
+    let (lat, lon) = text.split_once(',')?;
+    let lat = lat.trim().parse::<f64>().ok()?;
+    let lon = lon.trim().parse::<f64>().ok()?;
+    ((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)).then_some((lat, lon))
+}
+
+/// Great-circle distance between two coordinates, kilometers.
+pub fn haversine_km(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lat = lat2 - lat1;
+    let d_lon = (to.1 - from.1).to_radians();
+    let a = (d_lat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (d_lon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+pub fn km_to_miles(km: f64) -> f64 {
+    km / KM_PER_MILE
+}
+
+/// Initial great-circle bearing from one coordinate to another, degrees clockwise from north.
+pub fn initial_bearing(from: (f64, f64), to: (f64, f64)) -> f64 {
+    let (lat1, lat2) = (from.0.to_radians(), to.0.to_radians());
+    let d_lon = (to.1 - from.1).to_radians();
+    let y = d_lon.sin() * lat2.cos();
+    let x = lat1.cos() * lat2.sin() - lat1.sin() * lat2.cos() * d_lon.cos();
+    y.atan2(x).to_degrees().rem_euclid(360.0)
+}
+
+pub fn compass_point(bearing: f64) -> &'static str {
+    COMPASS_POINTS[((bearing.rem_euclid(360.0) + 11.25) / 22.5) as usize % COMPASS_POINTS.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PARIS: (f64, f64) = (48.8566, 2.3522);
+    const BERLIN: (f64, f64) = (52.52, 13.405);
+
+    fn assert_near(actual: f64, expected: f64, tolerance: f64) {
+        assert!(
+            (actual - expected).abs() <= tolerance,
+            "{} is not within {} of {}",
+            actual,
+            tolerance,
+            expected
+        );
+    }
+
+    #[test]
+    fn known_distances() {
+        assert_near(haversine_km(PARIS, BERLIN), 878.0, 2.0);
+        assert_near(haversine_km(BERLIN, PARIS), 878.0, 2.0);
+        assert_near(km_to_miles(haversine_km(PARIS, BERLIN)), 545.6, 1.5);
+        assert_eq!(haversine_km(PARIS, PARIS), 0.0);
+        // Pole to pole is half the circumference.
+        assert_near(
+            haversine_km((90.0, 0.0), (-90.0, 0.0)),
+            std::f64::consts::PI * EARTH_RADIUS_KM,
+            0.001,
+        );
+    }
+
+    #[test]
+    fn across_the_antimeridian() {
+        // Two degrees of the equator, not 358.
+        assert_near(haversine_km((0.0, 179.0), (0.0, -179.0)), 222.4, 0.1);
+        assert_near(initial_bearing((0.0, 179.0), (0.0, -179.0)), 90.0, 0.001);
+        assert_near(initial_bearing((0.0, -179.0), (0.0, 179.0)), 270.0, 0.001);
+    }
+
+    #[test]
+    fn known_bearings() {
+        assert_near(initial_bearing(PARIS, BERLIN), 58.2, 0.1);
+        assert_eq!(compass_point(initial_bearing(PARIS, BERLIN)), "ENE");
+        assert_near(initial_bearing((10.0, 20.0), (30.0, 20.0)), 0.0, 0.001);
+        assert_near(initial_bearing((30.0, 20.0), (10.0, 20.0)), 180.0, 0.001);
+    }
+
+    #[test]
+    fn compass_points_wrap() {
+        assert_eq!(compass_point(0.0), "N");
+        assert_eq!(compass_point(11.2), "N");
+        assert_eq!(compass_point(11.25), "NNE");
+        assert_eq!(compass_point(348.8), "N");
+        assert_eq!(compass_point(360.0), "N");
+        assert_eq!(compass_point(-45.0), "NW");
+        assert_eq!(compass_point(202.5), "SSW");
+    }
+
+    #[test]
+    fn parses_coordinates() {
+        assert_eq!(parse_coordinates("48.85, 2.35"), Some((48.85, 2.35)));
+        assert_eq!(parse_coordinates(" -90 ,-180 "), Some((-90.0, -180.0)));
+        assert_eq!(parse_coordinates("90.1,0"), None);
+        assert_eq!(parse_coordinates("0,180.5"), None);
+        assert_eq!(parse_coordinates("48.85 2.35"), None);
+        assert_eq!(parse_coordinates("Paris, France"), None);
+        assert_eq!(parse_coordinates("1,2,3"), None);
+    }
+}
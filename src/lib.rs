@@ -1,16 +1,30 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, sync::Arc};
 
+use action::tools::ToolOutput;
 use err::RKBServiceRequestErr;
 use resource::Resources;
-use serenity::all::{ChannelId, Context, EditMessage, GetMessages, Message, MessageId};
+use serenity::all::{
+    ChannelId, Context, EditMessage, GetMessages, Message, MessageId, Permissions, UserId,
+};
 use token::Tokens;
 use tracing::error;
 
 pub mod action;
+pub mod astro;
+pub mod cache;
+pub mod comfort;
 pub mod err;
+pub mod geo;
+pub mod llm;
+pub mod recurrence;
 pub mod resource;
+pub mod settings;
+pub mod storage;
+#[cfg(test)]
+mod stub;
 pub mod text;
 mod token;
+pub mod weather;
 
 #[derive(Debug, Clone)]
 pub struct RKBServiceRequest {
@@ -18,17 +32,20 @@ pub struct RKBServiceRequest {
     pub msg: Message,
     pub tkn: Tokens,
     pub rsc: Resources,
+    /// Collects what is sent while an action runs as a chat tool, to feed back to the model.
+    pub tool_output: Option<Arc<ToolOutput>>,
 }
 
 const ENTRY_STRING: &str = "?";
 
 impl RKBServiceRequest {
-    pub fn new(ctx: Context, msg: Message) -> Self {
+    pub fn new(ctx: Context, msg: Message, rsc: Resources) -> Self {
         RKBServiceRequest {
             ctx,
             msg,
             tkn: Tokens::default(),
-            rsc: Resources::default(),
+            rsc,
+            tool_output: None,
         }
     }
 
@@ -44,6 +61,57 @@ impl RKBServiceRequest {
         !self.msg.author.bot && !self.msg.author.system
     }
 
+    /// Whether the author may manage messages in this channel, according to the cached guild.
+    pub fn is_moderator(&self) -> bool {
+        self.author_permissions()
+            .is_some_and(|v| v.manage_messages())
+    }
+
+    /// Whether the author may manage the guild, according to the cached guild.
+    pub fn is_admin(&self) -> bool {
+        self.author_permissions().is_some_and(|v| v.manage_guild())
+    }
+
+    /// Whether `user_id` may manage the guild, fetching the member when it isn't cached.
+    pub async fn is_admin_user(&self, user_id: UserId) -> bool {
+        let Some(guild_id) = self.msg.guild_id else {
+            return false;
+        };
+        let Ok(member) = guild_id.member(&self.ctx, user_id).await else {
+            return false;
+        };
+        let Some(guild) = self.ctx.cache.guild(guild_id) else {
+            return false;
+        };
+        let channel_id = self.msg.channel_id;
+        guild
+            .channels
+            .get(&channel_id)
+            .or_else(|| guild.threads.iter().find(|v| v.id == channel_id))
+            .is_some_and(|channel| guild.user_permissions_in(channel, &member).manage_guild())
+    }
+
+    fn author_permissions(&self) -> Option<Permissions> {
+        let (guild_id, member) = (self.msg.guild_id?, self.msg.member.as_ref()?);
+        let guild = self.ctx.cache.guild(guild_id)?;
+        let channel_id = self.msg.channel_id;
+        let channel = guild
+            .channels
+            .get(&channel_id)
+            .or_else(|| guild.threads.iter().find(|v| v.id == channel_id))?;
+        Some(guild.partial_member_permissions_in(channel, self.msg.author.id, member))
+    }
+
+    /// Channel a thread was started in, `None` outside of threads.
+    pub fn parent_channel(&self) -> Option<ChannelId> {
+        let guild = self.ctx.cache.guild(self.msg.guild_id?)?;
+        guild
+            .threads
+            .iter()
+            .find(|v| v.id == self.msg.channel_id)?
+            .parent_id
+    }
+
     pub async fn handle_message(self) -> Result<(), RKBServiceRequestErr> {
         if !self.msg.content.starts_with(ENTRY_STRING) {
             return Ok(());
@@ -54,34 +122,40 @@ impl RKBServiceRequest {
         match action.as_str() {
             "help" | "" => rkb_binding.help().await?,
             "weather" | "temperature" | "temp" => rkb_binding.weather().await?,
+            "forecast" => rkb_binding.forecast().await?,
             "geo" => rkb_binding.geo().await?,
-            "chat" => rkb_binding.deepseek_chat(false, None).await?,
-            "reason" => rkb_binding.deepseek_chat(true, None).await?,
+            "aqi" | "air" => rkb_binding.aqi().await?,
+            "cache" => rkb_binding.weather_cache().await?,
+            "sun" => rkb_binding.sun().await?,
+            "moon" => rkb_binding.moon().await?,
+            "chat" => rkb_binding.chat(false, None).await?,
+            "reason" => rkb_binding.chat(true, None).await?,
+            "llm" | "model" => rkb_binding.llm().await?,
+            "persona" => rkb_binding.persona().await?,
+            "usage" => rkb_binding.usage().await?,
             // "test" => tokio::spawn(rkb_binding.test()),
+            "remind" | "reminder" => rkb_binding.remind().await?,
             "timer" => rkb_binding.timer().await?,
+            "timers" => rkb_binding.timers().await?,
+            "pomodoro" | "pomo" => rkb_binding.pomodoro().await?,
+            "stopwatch" => rkb_binding.stopwatch().await?,
             _ => rkb_binding.nonaction().await?,
         };
         Ok(())
     }
 
     pub async fn pinned_handle_message(self) -> bool {
-        let pinned_messages = self
-            .msg
-            .channel_id
-            .pins(&self.ctx.http)
-            .await
-            .expect("Bot missing read history permissions.");
-        let Some(pinned_message) = pinned_messages
-            .into_iter()
-            .find(|msg| msg.content.starts_with(ENTRY_STRING))
-        else {
+        let Some(pinned_message) = self.pinned_prompt().await else {
             return false;
         };
         let (pinned_action, pinned_content) = split_action(pinned_message.content);
-        let (_action, _content) = split_action(self.msg.content.clone());
+        let (action, _content) = split_action(self.msg.content.clone());
         let rkb_binding = self.clone();
+        // `reason` in a pinned chat channel answers with reasoning, under the same pre-prompt.
+        let reasoning = pinned_action == "reason"
+            || (self.msg.content.starts_with(ENTRY_STRING) && action == "reason");
         match pinned_action.as_str() {
-            "chat" => tokio::spawn(rkb_binding.deepseek_chat(false, Some(pinned_content))),
+            "chat" | "reason" => tokio::spawn(rkb_binding.chat(reasoning, Some(pinned_content))),
             _ => tokio::spawn(rkb_binding.nonaction_pinned()),
         };
         true
@@ -112,6 +186,16 @@ impl RKBServiceRequest {
         &self,
         responses: VecDeque<String>,
     ) -> Result<Message, RKBServiceRequestErr> {
+        if let Some(tool_output) = &self.tool_output {
+            tool_output
+                .replies
+                .lock()
+                .expect("Tool output lock poisoned.")
+                .extend(responses.iter().cloned());
+            if !tool_output.visible {
+                return Ok(Message::default());
+            }
+        }
         let mut latest_message = None;
         for response in &responses {
             latest_message = self
@@ -186,6 +270,17 @@ impl RKBServiceRequest {
     }
 }
 
+pub fn split_flags(content: &str) -> (String, Vec<String>) {
+    let (flags, words): (Vec<&str>, Vec<&str>) = content
+        .split_whitespace()
+        .partition(|word| word.starts_with("--"));
+    let flags = flags
+        .into_iter()
+        .map(|flag| flag.trim_start_matches("--").to_lowercase())
+        .collect();
+    (words.join(" "), flags)
+}
+
 pub fn split_action(message: String) -> (String, String) {
     let stripped_msg = message.trim_start_matches(ENTRY_STRING).to_string();
     stripped_msg
@@ -0,0 +1,253 @@
+pub mod ollama;
+pub mod openai;
+
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+use serenity::async_trait;
+use thiserror::Error;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    err::RKBServiceRequestErr,
+    settings::LlmSelection,
+    token::{TokenType, Tokens},
+    RKBServiceRequest,
+};
+use ollama::Ollama;
+use openai::OpenAiCompatible;
+
+pub const DEEPSEEK: &str = "deepseek";
+pub const OPENAI: &str = "openai";
+pub const OLLAMA: &str = "ollama";
+pub const PROVIDERS: [&str; 3] = [DEEPSEEK, OPENAI, OLLAMA];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to query llm provider")]
+    QueryError(&'static str),
+    #[error("failed to parse llm provider response")]
+    ParseError(&'static str),
+    #[error("llm provider returned no reply")]
+    EmptyReply(&'static str),
+    #[error("llm provider is not known")]
+    UnknownProvider(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ChatMessage {
+    pub role: Role,
+    pub content: String,
+    /// Tools an assistant message asked to call.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tool_calls: Vec<ToolCall>,
+    /// The call a tool message answers.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call: Option<ToolCall>,
+}
+
+impl ChatMessage {
+    pub fn new(role: Role, content: String) -> Self {
+        ChatMessage {
+            role,
+            content,
+            tool_calls: Vec::new(),
+            tool_call: None,
+        }
+    }
+
+    /// An assistant turn asking for `tool_calls`.
+    pub fn tool_request(content: String, tool_calls: Vec<ToolCall>) -> Self {
+        ChatMessage {
+            tool_calls,
+            ..ChatMessage::new(Role::Assistant, content)
+        }
+    }
+
+    /// The output of `tool_call`.
+    pub fn tool_result(tool_call: ToolCall, content: String) -> Self {
+        ChatMessage {
+            tool_call: Some(tool_call),
+            ..ChatMessage::new(Role::Tool, content)
+        }
+    }
+
+    pub fn system(content: String) -> Self {
+        ChatMessage::new(Role::System, content)
+    }
+
+    /// Estimated tokens, with a few for the role and message framing.
+    pub fn estimate_tokens(&self) -> usize {
+        estimate_tokens(&self.content) + 4
+    }
+}
+
+/// A function the model may call, with JSON schema parameters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ToolDefinition {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub parameters: serde_json::Value,
+}
+
+impl Serialize for ToolDefinition {
+    /// The `{"type": "function", "function": {..}}` shape OpenAI and Ollama share.
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serde_json::json!({
+            "type": "function",
+            "function": {
+                "name": self.name,
+                "description": self.description,
+                "parameters": self.parameters,
+            },
+        })
+        .serialize(serializer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    pub arguments: serde_json::Value,
+}
+
+/// Tokens billed for a reply.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ChatResponse {
+    pub content: String,
+    /// Chain of thought of reasoning models.
+    pub reasoning: Option<String>,
+    pub usage: Option<Usage>,
+    /// Tools the model asked to call before answering.
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Rough token count, about four ASCII characters per token and one per other character.
+pub fn estimate_tokens(text: &str) -> usize {
+    text.chars()
+        .map(|c| if c.is_ascii() { 1 } else { 4 })
+        .sum::<usize>()
+        .div_ceil(4)
+}
+
+/// Part of a streamed reply.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ChatDelta {
+    Content(String),
+    Reasoning(String),
+}
+
+#[async_trait]
+pub trait LlmProvider: std::fmt::Debug + Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn model(&self) -> &str;
+
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+    ) -> Result<ChatResponse, RKBServiceRequestErr>;
+
+    /// Sends parts of the reply as they arrive and returns the whole reply.
+    async fn stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        deltas: UnboundedSender<ChatDelta>,
+    ) -> Result<ChatResponse, RKBServiceRequestErr>;
+}
+
+impl RKBServiceRequest {
+    /// Provider and model set for this channel, else for the guild, else by `LLM_PROVIDER`.
+    pub fn llm_selection(&self) -> LlmSelection {
+        let settings = self.guild_settings();
+        settings
+            .channel_llm
+            .get(&self.msg.channel_id.to_string())
+            .filter(|v| v.provider.is_some())
+            .or(Some(&settings.llm).filter(|v| v.provider.is_some()))
+            .cloned()
+            .unwrap_or_else(|| LlmSelection {
+                provider: Some(
+                    self.tkn
+                        .get_or(&TokenType::LlmProvider, DEEPSEEK)
+                        .to_string(),
+                ),
+                ..LlmSelection::default()
+            })
+    }
+
+    /// Provider for chat, or for reasoning which picks the provider's reasoning model.
+    pub fn llm_provider(
+        &self,
+        reasoning: bool,
+    ) -> Result<Arc<dyn LlmProvider>, RKBServiceRequestErr> {
+        let selection = self.llm_selection();
+        let model = match reasoning {
+            true => selection.reasoning_model,
+            false => selection.model,
+        };
+        provider(
+            &self.tkn,
+            selection.provider.as_deref().unwrap_or(DEEPSEEK),
+            model,
+            reasoning,
+        )
+    }
+}
+
+pub fn provider(
+    tkn: &Tokens,
+    name: &str,
+    model: Option<String>,
+    reasoning: bool,
+) -> Result<Arc<dyn LlmProvider>, RKBServiceRequestErr> {
+    let provider: Arc<dyn LlmProvider> = match name {
+        DEEPSEEK => Arc::new(OpenAiCompatible::deepseek(tkn, model, reasoning)?),
+        OPENAI => Arc::new(OpenAiCompatible::openai(tkn, model, reasoning)),
+        OLLAMA => Arc::new(Ollama::new(tkn, model, reasoning)),
+        _ => Err(Error::UnknownProvider(name.to_string()))?,
+    };
+    Ok(provider)
+}
+
+/// Calls `f` with each line of a streamed body as chunks arrive, until it returns false.
+async fn for_each_line(
+    provider: &'static str,
+    mut response: reqwest::Response,
+    mut f: impl FnMut(&str) -> Result<bool, RKBServiceRequestErr>,
+) -> Result<(), RKBServiceRequestErr> {
+    let mut buffer = Vec::new();
+    while let Some(chunk) = response
+        .chunk()
+        .await
+        .map_err(|_| Error::QueryError(provider))?
+    {
+        buffer.extend_from_slice(&chunk);
+        while let Some(end) = buffer.iter().position(|v| *v == b'\n') {
+            let line = buffer.drain(..=end).collect::<Vec<u8>>();
+            if !f(String::from_utf8_lossy(&line).trim())? {
+                return Ok(());
+            }
+        }
+    }
+    f(String::from_utf8_lossy(&buffer).trim())?;
+    Ok(())
+}
@@ -0,0 +1,372 @@
+use serde::{Deserialize, Serialize};
+use serenity::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    for_each_line, ChatDelta, ChatMessage, ChatResponse, Error, LlmProvider, Role, ToolCall,
+    ToolDefinition, Usage,
+};
+use crate::{
+    err::RKBServiceRequestErr,
+    token::{TokenType, Tokens},
+};
+
+const PROVIDER: &str = super::OLLAMA;
+const DEFAULT_BASE_URL: &str = "http://localhost:11434";
+const DEFAULT_MODEL: &str = "llama3.2";
+
+/// A local Ollama server, through its native chat API.
+#[derive(Debug, Clone)]
+pub struct Ollama {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    /// Ask thinking models for their reasoning.
+    think: bool,
+}
+
+impl Ollama {
+    pub fn new(tkn: &Tokens, model: Option<String>, reasoning: bool) -> Self {
+        Ollama {
+            client: reqwest::Client::new(),
+            base_url: tkn
+                .get_or(&TokenType::OllamaUrl, DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            model: model.unwrap_or(
+                tkn.get_or(&TokenType::OllamaModel, DEFAULT_MODEL)
+                    .to_string(),
+            ),
+            think: reasoning,
+        }
+    }
+
+    async fn send(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        stream: bool,
+    ) -> Result<reqwest::Response, RKBServiceRequestErr> {
+        let body = OllamaChatRequest {
+            model: &self.model,
+            messages: messages.iter().map(OllamaMessageRequest::from).collect(),
+            tools,
+            stream,
+            think: self.think.then_some(true),
+        };
+        let response = self
+            .client
+            .post(format!("{}/api/chat", self.base_url))
+            .json(&body)
+            .send()
+            .await
+            .and_then(|v| v.error_for_status())
+            .map_err(|_| Error::QueryError(PROVIDER))?;
+        Ok(response)
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaChatRequest<'a> {
+    model: &'a str,
+    messages: Vec<OllamaMessageRequest<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tools: &'a [ToolDefinition],
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    think: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+struct OllamaMessageRequest<'a> {
+    role: Role,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCallJson>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<&'a str>,
+}
+
+impl<'a> From<&'a ChatMessage> for OllamaMessageRequest<'a> {
+    fn from(value: &'a ChatMessage) -> Self {
+        OllamaMessageRequest {
+            role: value.role,
+            content: &value.content,
+            tool_calls: value
+                .tool_calls
+                .iter()
+                .map(|v| OllamaToolCallJson {
+                    function: OllamaFunctionJson {
+                        name: v.name.clone(),
+                        arguments: v.arguments.clone(),
+                    },
+                })
+                .collect(),
+            tool_name: value.tool_call.as_ref().map(|v| v.name.as_str()),
+        }
+    }
+}
+
+/// Ollama sends whole calls with JSON arguments and without ids.
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCallJson {
+    function: OllamaFunctionJson,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionJson {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaChatJson {
+    #[serde(default)]
+    message: OllamaMessageJson,
+    #[serde(default)]
+    done: bool,
+    prompt_eval_count: Option<u64>,
+    eval_count: Option<u64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OllamaMessageJson {
+    #[serde(default)]
+    content: String,
+    thinking: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<OllamaToolCallJson>,
+}
+
+impl OllamaMessageJson {
+    /// Calls numbered after the `previous` ones, for replies to refer to.
+    fn tool_calls(&mut self, previous: usize) -> Vec<ToolCall> {
+        std::mem::take(&mut self.tool_calls)
+            .into_iter()
+            .enumerate()
+            .map(|(i, v)| ToolCall {
+                id: format!("call_{}", previous + i),
+                name: v.function.name,
+                arguments: v.function.arguments,
+            })
+            .collect()
+    }
+}
+
+impl OllamaChatJson {
+    fn usage(&self) -> Option<Usage> {
+        Some(Usage {
+            prompt_tokens: self.prompt_eval_count?,
+            completion_tokens: self.eval_count?,
+        })
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Ollama {
+    fn name(&self) -> &'static str {
+        PROVIDER
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+    ) -> Result<ChatResponse, RKBServiceRequestErr> {
+        let mut reply = self
+            .send(messages, tools, false)
+            .await?
+            .json::<OllamaChatJson>()
+            .await
+            .map_err(|_| Error::ParseError(PROVIDER))?;
+        let tool_calls = reply.message.tool_calls(0);
+        if reply.message.content.is_empty() && tool_calls.is_empty() {
+            Err(Error::EmptyReply(PROVIDER))?;
+        }
+        let usage = reply.usage();
+        Ok(ChatResponse {
+            content: reply.message.content,
+            reasoning: reply.message.thinking.filter(|v| !v.is_empty()),
+            usage,
+            tool_calls,
+        })
+    }
+
+    async fn stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        deltas: UnboundedSender<ChatDelta>,
+    ) -> Result<ChatResponse, RKBServiceRequestErr> {
+        let response = self.send(messages, tools, true).await?;
+        let mut reply = ChatResponse::default();
+        let mut reasoning = String::new();
+        // Newline delimited JSON, the last object carries `done` and the token counts.
+        for_each_line(PROVIDER, response, |line| {
+            if line.is_empty() {
+                return Ok(true);
+            }
+            let mut chunk = serde_json::from_str::<OllamaChatJson>(line)
+                .map_err(|_| Error::ParseError(PROVIDER))?;
+            let tool_calls = chunk.message.tool_calls(reply.tool_calls.len());
+            reply.tool_calls.extend(tool_calls);
+            if let Some(thinking) = chunk.message.thinking.clone().filter(|v| !v.is_empty()) {
+                reasoning += &thinking;
+                let _ = deltas.send(ChatDelta::Reasoning(thinking));
+            }
+            if !chunk.message.content.is_empty() {
+                reply.content += &chunk.message.content;
+                let _ = deltas.send(ChatDelta::Content(chunk.message.content.clone()));
+            }
+            if chunk.done {
+                reply.usage = chunk.usage();
+            }
+            Ok(!chunk.done)
+        })
+        .await?;
+        if reply.content.is_empty() && reply.tool_calls.is_empty() {
+            Err(Error::EmptyReply(PROVIDER))?;
+        }
+        reply.reasoning = (!reasoning.is_empty()).then_some(reasoning);
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::stub::{Route, Stub};
+
+    fn ollama(stub: &Stub, reasoning: bool) -> Ollama {
+        let tkn = Tokens::from_iter([(TokenType::OllamaUrl, stub.url.clone())]);
+        Ollama::new(&tkn, Some(String::from("qwen3")), reasoning)
+    }
+
+    fn json_lines(lines: &[serde_json::Value]) -> Route {
+        Route {
+            path: "/api/chat",
+            content_type: "application/x-ndjson",
+            body: lines.iter().map(|v| format!("{}\n", v)).collect(),
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_chat_reply_with_tool_calls() {
+        let stub = Stub::serve(vec![Route::json(
+            "/api/chat",
+            json!({
+                "message": {"role": "assistant", "content": "", "tool_calls": [
+                    {"function": {"name": "timer", "arguments": {"duration": "20m"}}},
+                    {"function": {"name": "geo", "arguments": {"query": "Oslo"}}},
+                ]},
+                "done": true, "prompt_eval_count": 30, "eval_count": 12,
+            }),
+        )])
+        .await;
+        let messages = [ChatMessage::new(Role::User, String::from("Timer 20m"))];
+        let response = ollama(&stub, false).chat(&messages, &[]).await.unwrap();
+        assert_eq!(response.tool_calls.len(), 2);
+        assert_eq!(response.tool_calls[0].id, "call_0");
+        assert_eq!(response.tool_calls[0].arguments, json!({"duration": "20m"}));
+        assert_eq!(response.tool_calls[1].name, "geo");
+        assert_eq!(
+            response.usage,
+            Some(Usage {
+                prompt_tokens: 30,
+                completion_tokens: 12
+            })
+        );
+        let body = stub.last_body();
+        assert_eq!(body["model"], "qwen3");
+        assert!(body.get("think").is_none());
+    }
+
+    #[tokio::test]
+    async fn sends_tool_results_by_name() {
+        let stub = Stub::serve(vec![Route::json(
+            "/api/chat",
+            json!({"message": {"role": "assistant", "content": "Set."}, "done": true}),
+        )])
+        .await;
+        let call = ToolCall {
+            id: String::from("call_0"),
+            name: String::from("timer"),
+            arguments: json!({"duration": "20m"}),
+        };
+        let messages = [
+            ChatMessage::tool_request(String::new(), vec![call.clone()]),
+            ChatMessage::tool_result(call, String::from("Timer set.")),
+        ];
+        let response = ollama(&stub, false).chat(&messages, &[]).await.unwrap();
+        assert_eq!(response.content, "Set.");
+        assert_eq!(response.usage, None);
+        let body = stub.last_body();
+        assert_eq!(
+            body["messages"][0]["tool_calls"][0]["function"]["arguments"],
+            json!({"duration": "20m"})
+        );
+        assert_eq!(body["messages"][1]["role"], "tool");
+        assert_eq!(body["messages"][1]["tool_name"], "timer");
+    }
+
+    #[tokio::test]
+    async fn streams_thinking_content_and_usage() {
+        let stub = Stub::serve(vec![json_lines(&[
+            json!({"message": {"role": "assistant", "content": "", "thinking": "Easy."}, "done": false}),
+            json!({"message": {"role": "assistant", "content": "4"}, "done": false}),
+            json!({"message": {"role": "assistant", "content": "2"}, "done": false}),
+            json!({
+                "message": {"role": "assistant", "content": ""},
+                "done": true, "prompt_eval_count": 8, "eval_count": 3,
+            }),
+        ])])
+        .await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let messages = [ChatMessage::new(Role::User, String::from("6 * 7?"))];
+        let response = ollama(&stub, true)
+            .stream(&messages, &[], sender)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "42");
+        assert_eq!(response.reasoning.as_deref(), Some("Easy."));
+        assert_eq!(response.usage.map(|v| v.prompt_tokens), Some(8));
+        assert_eq!(
+            receiver.try_recv().ok(),
+            Some(ChatDelta::Reasoning(String::from("Easy.")))
+        );
+        assert_eq!(stub.last_body()["think"], true);
+    }
+
+    #[tokio::test]
+    async fn numbers_streamed_tool_calls() {
+        let stub = Stub::serve(vec![json_lines(&[
+            json!({"message": {"role": "assistant", "content": "", "tool_calls": [
+                {"function": {"name": "weather", "arguments": {"location": "Oslo"}}},
+            ]}, "done": false}),
+            json!({"message": {"role": "assistant", "content": "", "tool_calls": [
+                {"function": {"name": "forecast", "arguments": {"location": "Oslo"}}},
+            ]}, "done": true}),
+        ])])
+        .await;
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let messages = [ChatMessage::new(Role::User, String::from("Oslo?"))];
+        let response = ollama(&stub, false)
+            .stream(&messages, &[], sender)
+            .await
+            .unwrap();
+        let ids = response
+            .tool_calls
+            .iter()
+            .map(|v| v.id.as_str())
+            .collect::<Vec<&str>>();
+        assert_eq!(ids, ["call_0", "call_1"]);
+    }
+}
@@ -0,0 +1,530 @@
+use serde::{Deserialize, Serialize};
+use serenity::async_trait;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{
+    for_each_line, ChatDelta, ChatMessage, ChatResponse, Error, LlmProvider, Role, ToolCall,
+    ToolDefinition, Usage,
+};
+use crate::{
+    err::RKBServiceRequestErr,
+    token::{TokenType, Tokens},
+};
+
+const DEEPSEEK_BASE_URL: &str = "https://api.deepseek.com";
+const DEEPSEEK_CHAT_MODEL: &str = "deepseek-chat";
+const DEEPSEEK_REASONING_MODEL: &str = "deepseek-reasoner";
+const OPENAI_BASE_URL: &str = "https://api.openai.com/v1";
+const OPENAI_CHAT_MODEL: &str = "gpt-4o-mini";
+const OPENAI_REASONING_MODEL: &str = "o4-mini";
+const STREAM_DONE: &str = "[DONE]";
+
+/// Any endpoint speaking the OpenAI chat completions API, DeepSeek included.
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatible {
+    client: reqwest::Client,
+    name: &'static str,
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+}
+
+impl OpenAiCompatible {
+    pub fn deepseek(
+        tkn: &Tokens,
+        model: Option<String>,
+        reasoning: bool,
+    ) -> Result<Self, RKBServiceRequestErr> {
+        let default_model = match reasoning {
+            true => DEEPSEEK_REASONING_MODEL,
+            false => DEEPSEEK_CHAT_MODEL,
+        };
+        Ok(OpenAiCompatible {
+            client: reqwest::Client::new(),
+            name: super::DEEPSEEK,
+            base_url: base_url(tkn, TokenType::DeepSeekUrl, DEEPSEEK_BASE_URL),
+            api_key: Some(tkn.get(&TokenType::DeepSeek)?.to_string()),
+            model: model.unwrap_or(default_model.to_string()),
+        })
+    }
+
+    /// OpenAI itself, or any compatible server at `OPENAI_URL`. The key is optional for local servers.
+    pub fn openai(tkn: &Tokens, model: Option<String>, reasoning: bool) -> Self {
+        let default_model = match reasoning {
+            true => tkn.get_or(&TokenType::OpenAiReasoningModel, OPENAI_REASONING_MODEL),
+            false => tkn.get_or(&TokenType::OpenAiModel, OPENAI_CHAT_MODEL),
+        };
+        OpenAiCompatible {
+            client: reqwest::Client::new(),
+            name: super::OPENAI,
+            base_url: base_url(tkn, TokenType::OpenAiUrl, OPENAI_BASE_URL),
+            api_key: tkn.get(&TokenType::OpenAi).ok().cloned(),
+            model: model.unwrap_or(default_model.to_string()),
+        }
+    }
+
+    async fn send(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        stream: bool,
+    ) -> Result<reqwest::Response, RKBServiceRequestErr> {
+        let body = ChatCompletionRequest {
+            model: &self.model,
+            messages: messages.iter().map(MessageRequest::from).collect(),
+            tools,
+            stream,
+            stream_options: stream.then_some(StreamOptions {
+                include_usage: true,
+            }),
+        };
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = request
+            .send()
+            .await
+            .and_then(|v| v.error_for_status())
+            .map_err(|_| Error::QueryError(self.name))?;
+        Ok(response)
+    }
+}
+
+fn base_url(tkn: &Tokens, key: TokenType, default: &str) -> String {
+    tkn.get_or(&key, default).trim_end_matches('/').to_string()
+}
+
+#[derive(Debug, Serialize)]
+struct ChatCompletionRequest<'a> {
+    model: &'a str,
+    messages: Vec<MessageRequest<'a>>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    tools: &'a [ToolDefinition],
+    stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    stream_options: Option<StreamOptions>,
+}
+
+#[derive(Debug, Serialize)]
+struct StreamOptions {
+    include_usage: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct MessageRequest<'a> {
+    role: Role,
+    content: &'a str,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<ToolCallRequest<'a>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<&'a str>,
+}
+
+#[derive(Debug, Serialize)]
+struct ToolCallRequest<'a> {
+    id: &'a str,
+    #[serde(rename = "type")]
+    kind: &'static str,
+    function: FunctionRequest<'a>,
+}
+
+/// Arguments travel as a JSON encoded string.
+#[derive(Debug, Serialize)]
+struct FunctionRequest<'a> {
+    name: &'a str,
+    arguments: String,
+}
+
+impl<'a> From<&'a ChatMessage> for MessageRequest<'a> {
+    fn from(value: &'a ChatMessage) -> Self {
+        MessageRequest {
+            role: value.role,
+            content: &value.content,
+            tool_calls: value
+                .tool_calls
+                .iter()
+                .map(|v| ToolCallRequest {
+                    id: &v.id,
+                    kind: "function",
+                    function: FunctionRequest {
+                        name: &v.name,
+                        arguments: v.arguments.to_string(),
+                    },
+                })
+                .collect(),
+            tool_call_id: value.tool_call.as_ref().map(|v| v.id.as_str()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionJson {
+    choices: Vec<ChoiceJson>,
+    usage: Option<UsageJson>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChoiceJson {
+    #[serde(alias = "delta")]
+    message: MessageJson,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct MessageJson {
+    content: Option<String>,
+    reasoning_content: Option<String>,
+    #[serde(default)]
+    tool_calls: Vec<ToolCallJson>,
+}
+
+/// A whole tool call, or a piece of one at `index` when streamed.
+#[derive(Debug, Deserialize)]
+struct ToolCallJson {
+    #[serde(default)]
+    index: usize,
+    id: Option<String>,
+    #[serde(default)]
+    function: FunctionJson,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct FunctionJson {
+    name: Option<String>,
+    arguments: Option<String>,
+}
+
+/// Tool calls assembled from streamed pieces, arguments still encoded.
+#[derive(Debug, Default)]
+struct PartialToolCall {
+    id: String,
+    name: String,
+    arguments: String,
+}
+
+impl From<PartialToolCall> for ToolCall {
+    fn from(value: PartialToolCall) -> Self {
+        ToolCall {
+            id: value.id,
+            name: value.name,
+            arguments: parse_arguments(&value.arguments),
+        }
+    }
+}
+
+/// Malformed arguments are passed on as a string for the tool to reject.
+fn parse_arguments(arguments: &str) -> serde_json::Value {
+    serde_json::from_str(arguments).unwrap_or(serde_json::Value::String(arguments.to_string()))
+}
+
+#[derive(Debug, Deserialize)]
+struct UsageJson {
+    prompt_tokens: u64,
+    completion_tokens: u64,
+}
+
+impl From<UsageJson> for Usage {
+    fn from(value: UsageJson) -> Self {
+        Usage {
+            prompt_tokens: value.prompt_tokens,
+            completion_tokens: value.completion_tokens,
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatible {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn model(&self) -> &str {
+        &self.model
+    }
+
+    async fn chat(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+    ) -> Result<ChatResponse, RKBServiceRequestErr> {
+        let completion = self
+            .send(messages, tools, false)
+            .await?
+            .json::<ChatCompletionJson>()
+            .await
+            .map_err(|_| Error::ParseError(self.name))?;
+        let message = completion
+            .choices
+            .into_iter()
+            .next()
+            .map(|v| v.message)
+            .ok_or(Error::EmptyReply(self.name))?;
+        let tool_calls = message
+            .tool_calls
+            .into_iter()
+            .map(|v| ToolCall {
+                id: v.id.unwrap_or_default(),
+                name: v.function.name.unwrap_or_default(),
+                arguments: parse_arguments(&v.function.arguments.unwrap_or_default()),
+            })
+            .collect::<Vec<ToolCall>>();
+        let content = message.content.unwrap_or_default();
+        if content.is_empty() && tool_calls.is_empty() {
+            Err(Error::EmptyReply(self.name))?;
+        }
+        Ok(ChatResponse {
+            content,
+            reasoning: message.reasoning_content.filter(|v| !v.is_empty()),
+            usage: completion.usage.map(Usage::from),
+            tool_calls,
+        })
+    }
+
+    async fn stream(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolDefinition],
+        deltas: UnboundedSender<ChatDelta>,
+    ) -> Result<ChatResponse, RKBServiceRequestErr> {
+        let response = self.send(messages, tools, true).await?;
+        let mut reply = ChatResponse::default();
+        let mut reasoning = String::new();
+        let mut tool_calls: Vec<PartialToolCall> = Vec::new();
+        // Server-sent events, one `data: {json}` line per chunk.
+        for_each_line(self.name, response, |line| {
+            let Some(data) = line.strip_prefix("data:").map(str::trim) else {
+                return Ok(true);
+            };
+            if data == STREAM_DONE {
+                return Ok(false);
+            }
+            let chunk = serde_json::from_str::<ChatCompletionJson>(data)
+                .map_err(|_| Error::ParseError(self.name))?;
+            if let Some(usage) = chunk.usage {
+                reply.usage = Some(usage.into());
+            }
+            let delta = chunk
+                .choices
+                .into_iter()
+                .next()
+                .map(|v| v.message)
+                .unwrap_or_default();
+            if let Some(content) = delta.reasoning_content.filter(|v| !v.is_empty()) {
+                reasoning += &content;
+                let _ = deltas.send(ChatDelta::Reasoning(content));
+            }
+            if let Some(content) = delta.content.filter(|v| !v.is_empty()) {
+                reply.content += &content;
+                let _ = deltas.send(ChatDelta::Content(content));
+            }
+            for piece in delta.tool_calls {
+                if tool_calls.len() <= piece.index {
+                    tool_calls.resize_with(piece.index + 1, PartialToolCall::default);
+                }
+                let call = &mut tool_calls[piece.index];
+                call.id += &piece.id.unwrap_or_default();
+                call.name += &piece.function.name.unwrap_or_default();
+                call.arguments += &piece.function.arguments.unwrap_or_default();
+            }
+            Ok(true)
+        })
+        .await?;
+        reply.tool_calls = tool_calls.into_iter().map(ToolCall::from).collect();
+        if reply.content.is_empty() && reply.tool_calls.is_empty() {
+            Err(Error::EmptyReply(self.name))?;
+        }
+        reply.reasoning = (!reasoning.is_empty()).then_some(reasoning);
+        Ok(reply)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::{
+        llm::DEEPSEEK,
+        stub::{Route, Stub},
+    };
+
+    fn deepseek(stub: &Stub, reasoning: bool) -> OpenAiCompatible {
+        let tkn = Tokens::from_iter([
+            (TokenType::DeepSeek, String::from("key")),
+            (TokenType::DeepSeekUrl, stub.url.clone()),
+        ]);
+        OpenAiCompatible::deepseek(&tkn, None, reasoning).unwrap()
+    }
+
+    fn weather_tool() -> ToolDefinition {
+        ToolDefinition {
+            name: "weather",
+            description: "Current weather at a place.",
+            parameters: json!({"type": "object", "properties": {"location": {"type": "string"}}}),
+        }
+    }
+
+    fn event_stream(chunks: &[serde_json::Value]) -> Route {
+        let mut body = chunks
+            .iter()
+            .map(|v| format!("data: {}\n\n", v))
+            .collect::<String>();
+        body += "data: [DONE]\n\n";
+        Route {
+            path: "/chat/completions",
+            content_type: "text/event-stream",
+            body,
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_chat_reply() {
+        let stub = Stub::serve(vec![Route::json(
+            "/chat/completions",
+            json!({
+                "choices": [{"message": {
+                    "role": "assistant", "content": "42.", "reasoning_content": "Six times seven.",
+                }}],
+                "usage": {"prompt_tokens": 12, "completion_tokens": 9, "total_tokens": 21},
+            }),
+        )])
+        .await;
+        let messages = [ChatMessage::new(Role::User, String::from("6 * 7?"))];
+        let response = deepseek(&stub, true).chat(&messages, &[]).await.unwrap();
+        assert_eq!(response.content, "42.");
+        assert_eq!(response.reasoning.as_deref(), Some("Six times seven."));
+        assert_eq!(
+            response.usage,
+            Some(Usage {
+                prompt_tokens: 12,
+                completion_tokens: 9
+            })
+        );
+        let body = stub.last_body();
+        assert_eq!(body["model"], DEEPSEEK_REASONING_MODEL);
+        assert_eq!(body["stream"], false);
+        assert!(body.get("tools").is_none());
+    }
+
+    #[tokio::test]
+    async fn parses_tool_calls_and_sends_results_back() {
+        let stub = Stub::serve(vec![Route::json(
+            "/chat/completions",
+            json!({"choices": [{"message": {"role": "assistant", "content": null, "tool_calls": [{
+                "id": "call_0", "type": "function",
+                "function": {"name": "weather", "arguments": "{\"location\":\"Paris\"}"},
+            }]}}]}),
+        )])
+        .await;
+        let provider = deepseek(&stub, false);
+        let messages = [ChatMessage::new(Role::User, String::from("Paris?"))];
+        let response = provider.chat(&messages, &[weather_tool()]).await.unwrap();
+        let call = ToolCall {
+            id: String::from("call_0"),
+            name: String::from("weather"),
+            arguments: json!({"location": "Paris"}),
+        };
+        assert_eq!(response.tool_calls, vec![call.clone()]);
+        assert_eq!(stub.last_body()["tools"][0]["function"]["name"], "weather");
+        let messages = [
+            ChatMessage::new(Role::User, String::from("Paris?")),
+            ChatMessage::tool_request(String::new(), vec![call.clone()]),
+            ChatMessage::tool_result(call, String::from("Sunny, 20°C")),
+        ];
+        let _ = provider.chat(&messages, &[]).await;
+        let body = stub.last_body();
+        assert_eq!(
+            body["messages"][1]["tool_calls"][0]["function"]["arguments"],
+            "{\"location\":\"Paris\"}"
+        );
+        assert_eq!(body["messages"][2]["role"], "tool");
+        assert_eq!(body["messages"][2]["tool_call_id"], "call_0");
+    }
+
+    #[tokio::test]
+    async fn streams_content_and_usage() {
+        let stub = Stub::serve(vec![event_stream(&[
+            json!({"choices": [{"delta": {"reasoning_content": "Hm."}}]}),
+            json!({"choices": [{"delta": {"content": "Hel"}}]}),
+            json!({"choices": [{"delta": {"content": "lo"}}]}),
+            json!({"choices": [], "usage": {"prompt_tokens": 5, "completion_tokens": 2}}),
+        ])])
+        .await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        let messages = [ChatMessage::new(Role::User, String::from("Hi"))];
+        let response = deepseek(&stub, false)
+            .stream(&messages, &[], sender)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "Hello");
+        assert_eq!(response.reasoning.as_deref(), Some("Hm."));
+        assert_eq!(response.usage.map(|v| v.completion_tokens), Some(2));
+        let mut deltas = Vec::new();
+        while let Ok(delta) = receiver.try_recv() {
+            deltas.push(delta);
+        }
+        assert_eq!(
+            deltas,
+            vec![
+                ChatDelta::Reasoning(String::from("Hm.")),
+                ChatDelta::Content(String::from("Hel")),
+                ChatDelta::Content(String::from("lo")),
+            ]
+        );
+        assert_eq!(stub.last_body()["stream_options"]["include_usage"], true);
+    }
+
+    #[tokio::test]
+    async fn assembles_streamed_tool_calls() {
+        let stub = Stub::serve(vec![event_stream(&[
+            json!({"choices": [{"delta": {"tool_calls": [{
+                "index": 0, "id": "call_0", "type": "function",
+                "function": {"name": "weather", "arguments": ""},
+            }]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{
+                "index": 0, "function": {"arguments": "{\"location\":"},
+            }]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{
+                "index": 0, "function": {"arguments": "\"Oslo\"}"},
+            }]}}]}),
+            json!({"choices": [{"delta": {"tool_calls": [{
+                "index": 1, "id": "call_1", "type": "function",
+                "function": {"name": "geo", "arguments": "not json"},
+            }]}}]}),
+        ])])
+        .await;
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        let messages = [ChatMessage::new(Role::User, String::from("Oslo?"))];
+        let response = deepseek(&stub, false)
+            .stream(&messages, &[weather_tool()], sender)
+            .await
+            .unwrap();
+        assert_eq!(response.content, "");
+        assert_eq!(response.tool_calls.len(), 2);
+        assert_eq!(response.tool_calls[0].id, "call_0");
+        assert_eq!(
+            response.tool_calls[0].arguments,
+            json!({"location": "Oslo"})
+        );
+        assert_eq!(response.tool_calls[1].arguments, json!("not json"));
+    }
+
+    #[tokio::test]
+    async fn rejects_empty_replies() {
+        let stub = Stub::serve(vec![Route::json(
+            "/chat/completions",
+            json!({"choices": [{"message": {"role": "assistant", "content": ""}}]}),
+        )])
+        .await;
+        let messages = [ChatMessage::new(Role::User, String::from("Hi"))];
+        let result = deepseek(&stub, false).chat(&messages, &[]).await;
+        assert!(matches!(
+            result,
+            Err(RKBServiceRequestErr::Llm(Error::EmptyReply(DEEPSEEK)))
+        ));
+    }
+}
@@ -1,21 +1,38 @@
+use std::sync::Arc;
+
 use anyhow::Context as _;
-use rustykelvinbot::RKBServiceRequest;
+use rustykelvinbot::{
+    action::{
+        recurring::rearm_reminders,
+        timer::{rearm_timers, snooze_timer},
+    },
+    resource::Resources,
+    storage::Storage,
+    RKBServiceRequest,
+};
 use serenity::async_trait;
+use serenity::model::application::Interaction;
 use serenity::model::channel::Message;
+use serenity::model::event::ChannelPinsUpdateEvent;
 use serenity::model::gateway::Ready;
 use serenity::prelude::*;
 use shuttle_runtime::SecretStore;
-use tracing::info;
+use tracing::{error, info, warn};
 
-struct Bot;
+struct Bot {
+    rsc: Resources,
+}
 
 #[async_trait]
 impl EventHandler for Bot {
     async fn message(&self, ctx: Context, msg: Message) {
-        let rkb = RKBServiceRequest::new(ctx, msg);
+        let rkb = RKBServiceRequest::new(ctx, msg, self.rsc.clone());
         if !rkb.is_user_message().await {
             return;
         }
+        if rkb.clone().thread_handle_message().await {
+            return;
+        }
         if rkb.clone().pinned_handle_message().await {
             return;
         }
@@ -24,8 +41,23 @@ impl EventHandler for Bot {
         };
     }
 
-    async fn ready(&self, _ctx: Context, ready: Ready) {
+    async fn ready(&self, ctx: Context, ready: Ready) {
         info!("{} is connected!", ready.user.name);
+        rearm_timers(ctx.clone(), self.rsc.clone()).await;
+        rearm_reminders(ctx, self.rsc.clone());
+    }
+
+    async fn channel_pins_update(&self, _ctx: Context, pin: ChannelPinsUpdateEvent) {
+        self.rsc.pinned_prompts.forget(pin.channel_id);
+    }
+
+    async fn interaction_create(&self, ctx: Context, interaction: Interaction) {
+        let Interaction::Component(component) = interaction else {
+            return;
+        };
+        if let Err(err) = snooze_timer(ctx, self.rsc.clone(), component).await {
+            error!("Failed to snooze timer: {:?}", err);
+        }
     }
 }
 
@@ -39,10 +71,24 @@ async fn serenity(
         .context("'DISCORD_TOKEN' was not found")?;
 
     // Set gateway intents, which decides what events the bot will be notified about
-    let intents = GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
+    let intents =
+        GatewayIntents::GUILDS | GatewayIntents::GUILD_MESSAGES | GatewayIntents::MESSAGE_CONTENT;
+
+    // Timers and settings only survive redeploys on a persistent volume.
+    let storage = match secrets.get("STORAGE_DIR") {
+        Some(dir) => Storage::new(dir),
+        None => {
+            warn!("STORAGE_DIR is not set, stored timers and settings are lost on redeploy.");
+            Storage::default()
+        }
+    };
+    let rsc = Resources {
+        storage: Arc::new(storage),
+        ..Resources::default()
+    };
 
     let client = Client::builder(&token, intents)
-        .event_handler(Bot)
+        .event_handler(Bot { rsc })
         .await
         .expect("Err creating client");
 
@@ -0,0 +1,260 @@
+use std::str::FromStr;
+
+use chrono::{DateTime, NaiveTime, TimeZone, Timelike, Utc, Weekday};
+use chrono_tz::Tz;
+use cron::Schedule;
+use thiserror::Error;
+
+use crate::text::natural_time::{parse_time_of_day, resolve_local};
+
+/// Time of day used when `every ...` is given without one.
+const DEFAULT_HOUR: u32 = 9;
+/// Upcoming wall clock times checked before giving up on a schedule.
+const OCCURRENCE_LOOKAHEAD: usize = 8;
+const DAY_NAMES: [&str; 7] = ["SUN", "MON", "TUE", "WED", "THU", "FRI", "SAT"];
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("expected `every day|weekday|weekend|monday,... [time]`")]
+    UnknownDays(String),
+    #[error("expected 5 cron fields, `minute hour day month weekday`")]
+    CronFieldCount(String),
+    #[error("cron expression is invalid")]
+    InvalidCron(String),
+}
+
+/// A schedule as a six field cron expression, with the wording it was created from.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub expression: String,
+    pub label: String,
+}
+
+impl Recurrence {
+    /// Parses `weekday 09:45 standup` following `every`. Returns the recurrence and the remaining text.
+    pub fn every(input: &str) -> Result<(Self, String), Error> {
+        let tokens = input.split_whitespace().collect::<Vec<&str>>();
+        let days = tokens
+            .first()
+            .and_then(|v| days_of_week(v))
+            .ok_or(Error::UnknownDays(input.to_string()))?;
+        let (time, consumed) = parse_time_of_day(&tokens[1..]).unwrap_or((
+            NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0).unwrap_or_default(),
+            0,
+        ));
+        let recurrence = Recurrence {
+            expression: format!("0 {} {} * * {}", time.minute(), time.hour(), days),
+            label: format!("every {} {}", tokens[0], time.format("%H:%M")),
+        };
+        Ok((recurrence, tokens[1 + consumed..].join(" ")))
+    }
+
+    /// Parses a standard 5 field cron expression, optionally quoted. Returns the recurrence and the remaining text.
+    pub fn cron(input: &str) -> Result<(Self, String), Error> {
+        let input = input.trim();
+        let (expression, rest) = match input.strip_prefix('"').and_then(|v| v.split_once('"')) {
+            Some((expression, rest)) => (expression.to_string(), rest.trim().to_string()),
+            None => {
+                let tokens = input.split_whitespace().collect::<Vec<&str>>();
+                let split = tokens.len().min(5);
+                (tokens[..split].join(" "), tokens[split..].join(" "))
+            }
+        };
+        let fields = expression.split_whitespace().collect::<Vec<&str>>();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(Error::CronFieldCount(expression));
+        };
+        let normalized = format!(
+            "0 {} {} {} {} {}",
+            minute,
+            hour,
+            day,
+            month,
+            day_of_week_names(weekday)
+        );
+        Schedule::from_str(&normalized).map_err(|_| Error::InvalidCron(expression.clone()))?;
+        let recurrence = Recurrence {
+            expression: normalized,
+            label: format!("cron {}", expression),
+        };
+        Ok((recurrence, rest))
+    }
+}
+
+/// First occurrence after `after`, evaluated on the wall clock of `tz`.
+/// Times skipped by a DST gap move forward an hour and repeated times fire once.
+pub fn next_occurrence(expression: &str, tz: Tz, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+    let schedule = Schedule::from_str(expression).ok()?;
+    let wall_clock = Utc.from_utc_datetime(&after.with_timezone(&tz).naive_local());
+    schedule
+        .after(&wall_clock)
+        .take(OCCURRENCE_LOOKAHEAD)
+        .filter_map(|v| resolve_local(tz, v.naive_utc()))
+        .find(|v| *v > after)
+}
+
+/// Cron day of week field for `day`, `weekday`, `weekend` or a comma separated list of days.
+fn days_of_week(token: &str) -> Option<String> {
+    let token = token.to_lowercase();
+    match token.as_str() {
+        "day" | "days" | "daily" => return Some(String::from("*")),
+        "weekday" | "weekdays" => return Some(String::from("MON-FRI")),
+        "weekend" | "weekends" => return Some(String::from("SAT,SUN")),
+        _ => (),
+    }
+    let days = token
+        .split(',')
+        .filter(|v| !v.is_empty())
+        .map(|v| {
+            v.parse::<Weekday>()
+                .or_else(|_| v.trim_end_matches('s').parse::<Weekday>())
+                .ok()
+                .map(|day| DAY_NAMES[day.num_days_from_sunday() as usize])
+        })
+        .collect::<Option<Vec<&str>>>()?;
+    (!days.is_empty()).then(|| days.join(","))
+}
+
+/// Replaces standard cron day numbers, where 0 and 7 are Sunday, with names.
+/// The cron crate numbers days from Sunday as 1, so numbers can not be passed through.
+fn day_of_week_names(field: &str) -> String {
+    field
+        .split(',')
+        .map(|item| {
+            let (range, step) = match item.split_once('/') {
+                Some((range, step)) => (range, Some(step)),
+                None => (item, None),
+            };
+            let range = range
+                .split('-')
+                .map(|v| match v.parse::<usize>() {
+                    Ok(day) if day <= 7 => DAY_NAMES[day % 7].to_string(),
+                    _ => v.to_string(),
+                })
+                .collect::<Vec<String>>()
+                .join("-");
+            match step {
+                Some(step) => format!("{}/{}", range, step),
+                None => range,
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn every_with_time() {
+        let (recurrence, rest) = Recurrence::every("weekday 09:45 standup").unwrap();
+        assert_eq!(recurrence.expression, "0 45 9 * * MON-FRI");
+        assert_eq!(recurrence.label, "every weekday 09:45");
+        assert_eq!(rest, "standup");
+        let (recurrence, rest) = Recurrence::every("day at 6pm water the plants").unwrap();
+        assert_eq!(recurrence.expression, "0 0 18 * * *");
+        assert_eq!(rest, "water the plants");
+    }
+
+    #[test]
+    fn every_defaults_to_morning() {
+        let (recurrence, rest) = Recurrence::every("weekend chores").unwrap();
+        assert_eq!(recurrence.expression, "0 0 9 * * SAT,SUN");
+        assert_eq!(rest, "chores");
+    }
+
+    #[test]
+    fn weekday_names() {
+        let (recurrence, _) = Recurrence::every("monday,Wed,fridays 8am").unwrap();
+        assert_eq!(recurrence.expression, "0 0 8 * * MON,WED,FRI");
+        let (recurrence, _) = Recurrence::every("sundays").unwrap();
+        assert_eq!(recurrence.expression, "0 0 9 * * SUN");
+        assert!(matches!(
+            Recurrence::every("fortnight 9am"),
+            Err(Error::UnknownDays(_))
+        ));
+        assert!(matches!(Recurrence::every(""), Err(Error::UnknownDays(_))));
+    }
+
+    #[test]
+    fn cron_forms() {
+        let (recurrence, rest) = Recurrence::cron("30 9 * * 1-5 standup").unwrap();
+        assert_eq!(recurrence.expression, "0 30 9 * * MON-FRI");
+        assert_eq!(recurrence.label, "cron 30 9 * * 1-5");
+        assert_eq!(rest, "standup");
+        let (recurrence, rest) = Recurrence::cron("\"0 18 * * 0,7\" weekly review").unwrap();
+        assert_eq!(recurrence.expression, "0 0 18 * * SUN,SUN");
+        assert_eq!(rest, "weekly review");
+        let (recurrence, _) = Recurrence::cron("*/15 * 1 * MON").unwrap();
+        assert_eq!(recurrence.expression, "0 */15 * 1 * MON");
+        let (recurrence, _) = Recurrence::cron("0 12 * * 1-5/2").unwrap();
+        assert_eq!(recurrence.expression, "0 0 12 * * MON-FRI/2");
+    }
+
+    #[test]
+    fn invalid_cron() {
+        assert!(matches!(
+            Recurrence::cron("\"0 9 * *\" tea"),
+            Err(Error::CronFieldCount(_))
+        ));
+        assert!(matches!(
+            Recurrence::cron("0 25 * * *"),
+            Err(Error::InvalidCron(_))
+        ));
+    }
+
+    #[test]
+    fn next_occurrence_on_the_wall_clock() {
+        let (recurrence, _) = Recurrence::every("day 9am").unwrap();
+        let tz = Tz::Europe__Berlin;
+        // 08:30 UTC is already past 9:00 in Berlin.
+        assert_eq!(
+            next_occurrence(&recurrence.expression, tz, utc(1, 10, 8, 30)),
+            Some(utc(1, 11, 8, 0))
+        );
+        assert_eq!(
+            next_occurrence(&recurrence.expression, tz, utc(1, 10, 7, 30)),
+            Some(utc(1, 10, 8, 0))
+        );
+    }
+
+    #[test]
+    fn next_occurrence_across_dst() {
+        let (recurrence, _) = Recurrence::every("day 9am").unwrap();
+        let tz = Tz::America__New_York;
+        // 9:00 is 14:00 UTC before the change on 2024-03-10 and 13:00 UTC after it.
+        assert_eq!(
+            next_occurrence(&recurrence.expression, tz, utc(3, 9, 15, 0)),
+            Some(utc(3, 10, 13, 0))
+        );
+        // 2:30 doesn't exist that night and moves to 3:30.
+        let (recurrence, _) = Recurrence::every("day 2:30").unwrap();
+        assert_eq!(
+            next_occurrence(&recurrence.expression, tz, utc(3, 10, 5, 0)),
+            Some(utc(3, 10, 7, 30))
+        );
+        // 1:30 happens twice on 2024-11-03 and fires once.
+        let (recurrence, _) = Recurrence::every("day 1:30").unwrap();
+        let first = next_occurrence(&recurrence.expression, tz, utc(11, 3, 4, 0)).unwrap();
+        assert_eq!(first, utc(11, 3, 5, 30));
+        assert_eq!(
+            next_occurrence(&recurrence.expression, tz, first),
+            Some(utc(11, 4, 6, 30))
+        );
+    }
+
+    #[test]
+    fn impossible_schedule_never_comes_up() {
+        let (recurrence, _) = Recurrence::cron("0 9 30 2 *").unwrap();
+        assert_eq!(
+            next_occurrence(&recurrence.expression, Tz::UTC, utc(1, 1, 0, 0)),
+            None
+        );
+    }
+}
@@ -1,4 +1,23 @@
-#[derive(Debug, Default, Clone, PartialEq, PartialOrd)]
+use std::sync::{atomic::AtomicBool, Arc};
+
+use crate::{
+    action::{chat::PinnedPromptCache, history::MessageCache, timer::TimerRegistry},
+    storage::Storage,
+    weather::cache::WeatherCache,
+};
+
+/// State shared by every request for the lifetime of the bot.
+#[derive(Debug, Default, Clone)]
 pub struct Resources {
-    // pub active_timers: Vec<DateTime<Utc>>,
+    pub weather_cache: Arc<WeatherCache>,
+    pub storage: Arc<Storage>,
+    pub timers: Arc<TimerRegistry>,
+    /// Recurring reminder tasks, keyed by reminder id.
+    pub reminders: Arc<TimerRegistry>,
+    /// Set once persisted timers have been re-armed, `ready` fires again on reconnect.
+    pub timers_rearmed: Arc<AtomicBool>,
+    /// Messages fetched while walking reply chains.
+    pub message_cache: Arc<MessageCache>,
+    /// Pinned prompts of channels, forgotten when their pins change.
+    pub pinned_prompts: Arc<PinnedPromptCache>,
 }
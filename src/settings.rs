@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use serde::{Deserialize, Serialize};
+use serenity::all::WebhookId;
+use thiserror::Error;
+
+use crate::{action::persona::Persona, err::RKBServiceRequestErr, RKBServiceRequest};
+
+const SETTINGS_DOCUMENT: &str = "guilds";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("guild settings can only be changed in a guild")]
+    NotInGuild,
+}
+
+/// Per guild configuration, changed by moderators.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GuildSettings {
+    /// Longest timer allowed, seconds.
+    pub max_timer_seconds: Option<u64>,
+    /// Chat provider and models for the guild.
+    #[serde(default)]
+    pub llm: LlmSelection,
+    /// Chat provider and models overriding the guild's, keyed by channel id.
+    #[serde(default)]
+    pub channel_llm: HashMap<String, LlmSelection>,
+    /// Leave the chain of thought out of `reason` answers.
+    #[serde(default)]
+    pub hide_reasoning: bool,
+    /// Chat personas keyed by lowercase name.
+    #[serde(default)]
+    pub personas: HashMap<String, Persona>,
+    /// Lowercase persona names keyed by channel id.
+    #[serde(default)]
+    pub channel_personas: HashMap<String, String>,
+    /// Webhooks personas speak through, keyed by channel id.
+    #[serde(default)]
+    pub persona_webhooks: HashMap<String, WebhookId>,
+    /// Chat tokens the guild may use per UTC day.
+    pub daily_token_quota: Option<u64>,
+    /// Chat tokens each user may use per UTC day.
+    pub user_daily_token_quota: Option<u64>,
+}
+
+/// A chat provider, with models overriding the provider's defaults.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LlmSelection {
+    pub provider: Option<String>,
+    pub model: Option<String>,
+    pub reasoning_model: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SettingsStore {
+    guilds: HashMap<String, GuildSettings>,
+}
+
+impl RKBServiceRequest {
+    /// Settings of the guild the message was sent in, defaults in direct messages.
+    pub fn guild_settings(&self) -> GuildSettings {
+        let Some(guild_id) = self.msg.guild_id else {
+            return GuildSettings::default();
+        };
+        self.rsc
+            .storage
+            .load::<SettingsStore>(SETTINGS_DOCUMENT)
+            .guilds
+            .remove(&guild_id.to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn update_guild_settings<R>(
+        &self,
+        f: impl FnOnce(&mut GuildSettings) -> R,
+    ) -> Result<R, RKBServiceRequestErr> {
+        let guild_id = self.msg.guild_id.ok_or(Error::NotInGuild)?;
+        self.rsc
+            .storage
+            .update(SETTINGS_DOCUMENT, |store: &mut SettingsStore| {
+                f(store.guilds.entry(guild_id.to_string()).or_default())
+            })
+    }
+}
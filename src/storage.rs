@@ -0,0 +1,176 @@
+use std::{fs, path::PathBuf, sync::Mutex};
+
+use serde::{de::DeserializeOwned, Serialize};
+use thiserror::Error;
+use tracing::error;
+
+use crate::err::RKBServiceRequestErr;
+
+/// Local to the deployment, set `STORAGE_DIR` to a persistent volume so documents survive
+/// redeploys.
+const DEFAULT_STORAGE_DIR: &str = "./data";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to serialize storage document")]
+    Serialize(String),
+    #[error("failed to write storage document")]
+    Write(String),
+    #[error("storage document can not be parsed, it is kept as is")]
+    Parse(String),
+}
+
+/// Named TOML documents persisted under the data directory.
+#[derive(Debug)]
+pub struct Storage {
+    lock: Mutex<()>,
+    dir: PathBuf,
+}
+
+impl Default for Storage {
+    fn default() -> Self {
+        Storage::new(DEFAULT_STORAGE_DIR)
+    }
+}
+
+impl Storage {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Storage {
+            lock: Mutex::new(()),
+            dir: dir.into(),
+        }
+    }
+
+    fn path(&self, name: &str) -> PathBuf {
+        self.dir.join(format!("{}.toml", name))
+    }
+
+    /// Reads a document, falling back to its default when missing or unreadable.
+    pub fn load<T: DeserializeOwned + Default>(&self, name: &str) -> T {
+        self.read(name).unwrap_or_else(|err| {
+            error!("Storage document {} can not be parsed: {:?}", name, err);
+            T::default()
+        })
+    }
+
+    /// Reads a document, its default when missing.
+    fn read<T: DeserializeOwned + Default>(&self, name: &str) -> Result<T, Error> {
+        let Ok(content) = fs::read_to_string(self.path(name)) else {
+            return Ok(T::default());
+        };
+        toml::from_str(&content).map_err(|err| Error::Parse(format!("{}: {}", name, err)))
+    }
+
+    /// Loads, mutates and writes back a document while holding the storage lock. A document that
+    /// can not be parsed is copied to a `.bak` file and left alone instead of being overwritten.
+    pub fn update<T, R>(
+        &self,
+        name: &str,
+        f: impl FnOnce(&mut T) -> R,
+    ) -> Result<R, RKBServiceRequestErr>
+    where
+        T: Serialize + DeserializeOwned + Default,
+    {
+        let _guard = self.lock.lock().expect("Storage lock poisoned.");
+        let mut document = match self.read::<T>(name) {
+            Ok(document) => document,
+            Err(err) => {
+                let path = self.path(name);
+                if let Err(backup_err) = fs::copy(&path, path.with_extension("toml.bak")) {
+                    error!(
+                        "Failed to back up storage document {}: {:?}",
+                        name, backup_err
+                    );
+                }
+                return Err(err.into());
+            }
+        };
+        let result = f(&mut document);
+        let content = toml::to_string(&document).map_err(|_| Error::Serialize(name.to_string()))?;
+        let path = self.path(name);
+        let staging_path = path.with_extension("toml.tmp");
+        fs::create_dir_all(&self.dir)
+            .and_then(|_| fs::write(&staging_path, content))
+            .and_then(|_| fs::rename(&staging_path, &path))
+            .map_err(|_| Error::Write(name.to_string()))?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{TimeZone, Utc};
+    use serenity::all::{ChannelId, MessageId, UserId};
+
+    use super::*;
+    use crate::action::timer::{Timer, TimerStore, TIMERS_DOCUMENT};
+
+    /// Storage in a fresh directory of its own.
+    fn storage(test: &str) -> Storage {
+        let dir = std::env::temp_dir().join(format!("rkb-storage-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Storage::new(dir)
+    }
+
+    #[test]
+    fn timer_store_round_trip() {
+        let storage = storage("round-trip");
+        let timer = Timer {
+            id: 7,
+            channel_id: ChannelId::new(10),
+            author_id: UserId::new(20),
+            dob: Utc.with_ymd_and_hms(2024, 3, 6, 14, 30, 0).unwrap(),
+            deadline: Utc.with_ymd_and_hms(2024, 3, 6, 16, 0, 0).unwrap(),
+            recalled_message: String::from("tea \"quoted\"\nsecond line"),
+            pinned_message_id: Some(MessageId::new(30)),
+            dm: true,
+        };
+        let next_id = storage
+            .update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+                store.next_id = timer.id;
+                store.timers.push(timer.clone());
+                store.pin_channels.insert(timer.channel_id);
+                store.next_id
+            })
+            .unwrap();
+        assert_eq!(next_id, 7);
+        let store = storage.load::<TimerStore>(TIMERS_DOCUMENT);
+        assert_eq!(store.next_id, 7);
+        assert_eq!(store.timers, vec![timer]);
+        assert!(store.fired.is_empty());
+        assert_eq!(
+            store.pin_channels.into_iter().collect::<Vec<_>>(),
+            [ChannelId::new(10)]
+        );
+    }
+
+    #[test]
+    fn missing_document_is_default() {
+        let storage = storage("missing");
+        let store = storage.load::<TimerStore>(TIMERS_DOCUMENT);
+        assert_eq!(store.next_id, 0);
+        assert!(store.timers.is_empty());
+    }
+
+    #[test]
+    fn corrupt_document_is_not_overwritten() {
+        let storage = storage("corrupt");
+        let corrupt = "next_id = 3\ntimers = [oops\n";
+        fs::create_dir_all(&storage.dir).unwrap();
+        fs::write(storage.path(TIMERS_DOCUMENT), corrupt).unwrap();
+        assert_eq!(storage.load::<TimerStore>(TIMERS_DOCUMENT).next_id, 0);
+        let updated = storage.update(TIMERS_DOCUMENT, |store: &mut TimerStore| {
+            store.next_id += 1;
+        });
+        assert!(matches!(
+            updated,
+            Err(RKBServiceRequestErr::Storage(Error::Parse(_)))
+        ));
+        let path = storage.path(TIMERS_DOCUMENT);
+        assert_eq!(fs::read_to_string(&path).unwrap(), corrupt);
+        assert_eq!(
+            fs::read_to_string(path.with_extension("toml.bak")).unwrap(),
+            corrupt
+        );
+    }
+}
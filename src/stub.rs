@@ -0,0 +1,111 @@
+use std::sync::{Arc, Mutex};
+
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+/// A canned response for requests whose path starts with `path`.
+pub struct Route {
+    pub path: &'static str,
+    pub content_type: &'static str,
+    pub body: String,
+}
+
+impl Route {
+    pub fn json(path: &'static str, body: serde_json::Value) -> Self {
+        Route {
+            path,
+            content_type: "application/json",
+            body: body.to_string(),
+        }
+    }
+}
+
+/// Local HTTP server standing in for a provider's API.
+pub struct Stub {
+    pub url: String,
+    /// Request lines and bodies received, in order.
+    pub requests: Arc<Mutex<Vec<(String, String)>>>,
+}
+
+impl Stub {
+    /// Serves `routes` on a free port, requests matching none get a 404.
+    pub async fn serve(routes: Vec<Route>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Stub server can not bind.");
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let routes = Arc::new(routes);
+        let received = requests.clone();
+        tokio::spawn(async move {
+            while let Ok((socket, _)) = listener.accept().await {
+                tokio::spawn(respond(socket, routes.clone(), received.clone()));
+            }
+        });
+        Stub { url, requests }
+    }
+
+    /// Body of the last request received.
+    pub fn last_body(&self) -> serde_json::Value {
+        let requests = self.requests.lock().unwrap();
+        let (_, body) = requests.last().expect("Stub server received no request.");
+        serde_json::from_str(body).expect("Request body is not JSON.")
+    }
+}
+
+async fn respond(
+    mut socket: TcpStream,
+    routes: Arc<Vec<Route>>,
+    received: Arc<Mutex<Vec<(String, String)>>>,
+) {
+    let mut buffer = Vec::new();
+    let mut chunk = [0; 4096];
+    // Headers, then as much body as `content-length` announces.
+    let (head, body_start) = loop {
+        let Ok(read) = socket.read(&mut chunk).await else {
+            return;
+        };
+        if read == 0 {
+            return;
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(end) = buffer.windows(4).position(|v| v == b"\r\n\r\n") {
+            break (String::from_utf8_lossy(&buffer[..end]).to_string(), end + 4);
+        }
+    };
+    let length = head
+        .lines()
+        .find_map(|v| {
+            let (name, value) = v.split_once(':')?;
+            name.eq_ignore_ascii_case("content-length")
+                .then(|| value.trim().parse::<usize>().ok())?
+        })
+        .unwrap_or_default();
+    while buffer.len() < body_start + length {
+        match socket.read(&mut chunk).await {
+            Ok(0) | Err(_) => break,
+            Ok(read) => buffer.extend_from_slice(&chunk[..read]),
+        }
+    }
+    let request_line = head.lines().next().unwrap_or_default().to_string();
+    let body = String::from_utf8_lossy(&buffer[body_start..]).to_string();
+    let path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or_default()
+        .to_string();
+    received.lock().unwrap().push((request_line, body));
+    let response = match routes.iter().find(|v| path.starts_with(v.path)) {
+        Some(route) => format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            route.content_type,
+            route.body.len(),
+            route.body
+        ),
+        None => String::from("HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"),
+    };
+    let _ = socket.write_all(response.as_bytes()).await;
+    let _ = socket.shutdown().await;
+}
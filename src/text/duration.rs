@@ -0,0 +1,254 @@
+use std::time::Duration;
+
+use chrono::TimeDelta;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("a duration is required, like `10m`, `1h 30m` or `PT1H30M`")]
+    Empty,
+    #[error("`{0}` is missing a unit, like `{0}m`")]
+    MissingUnit(String),
+    #[error("`{0}` in `{1}` is not a time unit")]
+    UnknownUnit(String, String),
+    #[error("`{0}` is not a number")]
+    InvalidNumber(String),
+    #[error("`{0}` is not an ISO-8601 duration, like `PT1H30M`")]
+    InvalidIso(String),
+    #[error("`{0}` is too long")]
+    Overflow(String),
+}
+
+/// Parses a leading duration such as `1h30m`, `1.5h`, `90 min`, `2 weeks and 3 days` or `PT1H30M`.
+/// Returns the duration and the number of tokens used. Errors point at the first token when it
+/// is not a duration, later tokens that are not a duration end it instead.
+pub fn parse_duration(tokens: &[&str]) -> Result<(TimeDelta, usize), Error> {
+    let mut delta = TimeDelta::zero();
+    let (mut i, mut end) = (0, 0);
+    while i < tokens.len() {
+        let token = tokens[i].trim_end_matches(',');
+        let next = tokens.get(i + 1).map(|v| v.trim_end_matches(','));
+        if i > 0 && token.eq_ignore_ascii_case("and") {
+            i += 1;
+            continue;
+        }
+        let piece = match (token.to_lowercase().as_str(), next.and_then(unit_seconds)) {
+            ("a" | "an", Some(seconds)) => scale(1.0, seconds, token).map(|v| (v, 2)),
+            (amount, Some(seconds)) if is_number(amount) => {
+                let amount = amount
+                    .parse::<f64>()
+                    .map_err(|_| Error::InvalidNumber(token.to_string()))?;
+                scale(amount, seconds, token).map(|v| (v, 2))
+            }
+            _ => parse_token(token).map(|v| (v, 1)),
+        };
+        match piece {
+            Ok((piece, consumed)) => {
+                delta = delta
+                    .checked_add(&piece)
+                    .ok_or(Error::Overflow(token.to_string()))?;
+                i += consumed;
+                end = i;
+            }
+            Err(err) if i == 0 => return Err(err),
+            Err(_) => break,
+        }
+    }
+    match delta.is_zero() {
+        true => Err(Error::Empty),
+        // A trailing `and` belongs to the remaining text.
+        false => Ok((delta, end)),
+    }
+}
+
+/// Compact form such as `1d2h30m`.
+pub fn format_duration(duration: Duration) -> String {
+    let mut remainder = duration.as_secs();
+    let mut formatted = String::new();
+    for (seconds, unit) in [(86400, "d"), (3600, "h"), (60, "m"), (1, "s")] {
+        if remainder >= seconds {
+            formatted += &format!("{}{}", remainder / seconds, unit);
+            remainder %= seconds;
+        }
+    }
+    match formatted.is_empty() {
+        true => String::from("0s"),
+        false => formatted,
+    }
+}
+
+/// A single token of number and unit pairs, `1h30m`, or an ISO-8601 duration.
+fn parse_token(token: &str) -> Result<TimeDelta, Error> {
+    let upper = token.to_uppercase();
+    if upper
+        .strip_prefix('P')
+        .is_some_and(|v| v.starts_with(|c: char| c.is_ascii_digit() || c == 'T'))
+    {
+        return parse_iso(token, &upper);
+    }
+    let mut delta = TimeDelta::zero();
+    let mut rest = token;
+    while !rest.is_empty() {
+        let split = rest
+            .find(|c: char| !is_number_char(c))
+            .unwrap_or(rest.len());
+        let (amount, tail) = rest.split_at(split);
+        let unit_end = tail.find(is_number_char).unwrap_or(tail.len());
+        let (unit, tail) = tail.split_at(unit_end);
+        if amount.is_empty() {
+            return Err(Error::InvalidNumber(token.to_string()));
+        }
+        if unit.is_empty() {
+            return Err(Error::MissingUnit(token.to_string()));
+        }
+        let seconds =
+            unit_seconds(unit).ok_or(Error::UnknownUnit(unit.to_string(), token.to_string()))?;
+        let amount = amount
+            .parse::<f64>()
+            .map_err(|_| Error::InvalidNumber(token.to_string()))?;
+        delta = delta
+            .checked_add(&scale(amount, seconds, token)?)
+            .ok_or(Error::Overflow(token.to_string()))?;
+        rest = tail;
+    }
+    Ok(delta)
+}
+
+/// `P[nW][nD][T[nH][nM][nS]]`, years and months are rejected as they have no fixed length.
+fn parse_iso(token: &str, upper: &str) -> Result<TimeDelta, Error> {
+    let invalid = || Error::InvalidIso(token.to_string());
+    let mut delta = TimeDelta::zero();
+    let mut amount = String::new();
+    let mut time = false;
+    for c in upper.chars().skip(1) {
+        let seconds = match (c, time) {
+            ('T', false) if amount.is_empty() => {
+                time = true;
+                continue;
+            }
+            (c, _) if is_number_char(c) || c == ',' => {
+                amount.push(if c == ',' { '.' } else { c });
+                continue;
+            }
+            ('W', false) => 604800,
+            ('D', false) => 86400,
+            ('H', true) => 3600,
+            ('M', true) => 60,
+            ('S', true) => 1,
+            _ => return Err(invalid()),
+        };
+        let value = amount.parse::<f64>().map_err(|_| invalid())?;
+        delta = delta
+            .checked_add(&scale(value, seconds, token)?)
+            .ok_or(Error::Overflow(token.to_string()))?;
+        amount.clear();
+    }
+    match amount.is_empty() {
+        true => Ok(delta),
+        false => Err(invalid()),
+    }
+}
+
+fn scale(amount: f64, seconds: i64, token: &str) -> Result<TimeDelta, Error> {
+    let milliseconds = amount * seconds as f64 * 1000.0;
+    if !milliseconds.is_finite() || milliseconds.abs() >= i64::MAX as f64 {
+        return Err(Error::Overflow(token.to_string()));
+    }
+    TimeDelta::try_milliseconds(milliseconds as i64).ok_or(Error::Overflow(token.to_string()))
+}
+
+fn is_number_char(c: char) -> bool {
+    c.is_ascii_digit() || c == '.'
+}
+
+fn is_number(token: &str) -> bool {
+    !token.is_empty() && token.chars().all(is_number_char)
+}
+
+fn unit_seconds(unit: &str) -> Option<i64> {
+    match unit.to_lowercase().as_str() {
+        "s" | "sec" | "secs" | "second" | "seconds" => Some(1),
+        "m" | "min" | "mins" | "minute" | "minutes" => Some(60),
+        "h" | "hr" | "hrs" | "hour" | "hours" => Some(3600),
+        "d" | "day" | "days" => Some(86400),
+        "w" | "wk" | "wks" | "week" | "weeks" => Some(604800),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(input: &str) -> Result<(TimeDelta, usize), Error> {
+        parse_duration(&input.split_whitespace().collect::<Vec<&str>>())
+    }
+
+    #[test]
+    fn fractional_hours() {
+        let (delta, consumed) = parse("1.5h").unwrap();
+        assert_eq!(delta, TimeDelta::minutes(90));
+        assert_eq!(consumed, 1);
+    }
+
+    #[test]
+    fn minutes_with_long_unit() {
+        assert_eq!(parse("90min").unwrap(), (TimeDelta::minutes(90), 1));
+        assert_eq!(parse("90 min").unwrap(), (TimeDelta::minutes(90), 2));
+    }
+
+    #[test]
+    fn pieces_add_up() {
+        assert_eq!(parse("1h 30m").unwrap(), (TimeDelta::minutes(90), 2));
+        assert_eq!(parse("1h30m").unwrap(), (TimeDelta::minutes(90), 1));
+        assert_eq!(
+            parse("2 weeks and 3 days").unwrap(),
+            (TimeDelta::days(17), 5)
+        );
+    }
+
+    #[test]
+    fn iso_8601() {
+        assert_eq!(parse("PT1H30M").unwrap(), (TimeDelta::minutes(90), 1));
+        assert_eq!(parse("P1DT12H").unwrap(), (TimeDelta::hours(36), 1));
+        assert!(matches!(parse("P1Y"), Err(Error::InvalidIso(_))));
+        assert!(matches!(parse("PT1H30"), Err(Error::InvalidIso(_))));
+    }
+
+    #[test]
+    fn remaining_text_is_left() {
+        let (delta, consumed) = parse("10m tea is ready and 5 more").unwrap();
+        assert_eq!(delta, TimeDelta::minutes(10));
+        assert_eq!(consumed, 1);
+        // A trailing `and` is not part of the duration.
+        assert_eq!(parse("10m and tea").unwrap(), (TimeDelta::minutes(10), 1));
+    }
+
+    #[test]
+    fn bare_number_is_missing_unit() {
+        assert!(matches!(parse("10"), Err(Error::MissingUnit(v)) if v == "10"));
+        assert!(matches!(parse("10x"), Err(Error::UnknownUnit(u, _)) if u == "x"));
+        assert!(matches!(parse(""), Err(Error::Empty)));
+        assert!(matches!(parse("0m"), Err(Error::Empty)));
+    }
+
+    #[test]
+    fn huge_durations_overflow() {
+        assert!(matches!(parse("99999999999999w"), Err(Error::Overflow(_))));
+        assert!(matches!(
+            parse("PT99999999999999999999H"),
+            Err(Error::Overflow(_))
+        ));
+        assert!(matches!(
+            parse("9999999999999w 9999999999999w"),
+            Err(Error::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn formats_compactly() {
+        assert_eq!(format_duration(Duration::from_secs(95_400)), "1d2h30m");
+        assert_eq!(format_duration(Duration::from_secs(59)), "59s");
+        assert_eq!(format_duration(Duration::ZERO), "0s");
+    }
+}
@@ -1 +1,3 @@
+pub mod duration;
 pub mod markdown;
+pub mod natural_time;
@@ -0,0 +1,305 @@
+use chrono::{
+    DateTime, Datelike, Days, NaiveDate, NaiveDateTime, NaiveTime, TimeDelta, TimeZone, Utc,
+    Weekday,
+};
+use chrono_tz::Tz;
+
+use crate::text::duration::parse_duration;
+
+/// Time of day used when only a date is given.
+const DEFAULT_HOUR: u32 = 9;
+/// Years searched for the next `MM/DD`, enough to reach a February 29.
+const MONTH_DAY_LOOKAHEAD: i32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Component {
+    Date(DateSpec),
+    Time(NaiveTime),
+    Zone(Tz),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum DateSpec {
+    Today,
+    Tomorrow,
+    Weekday(Weekday),
+    Absolute(NaiveDate),
+    /// `MM/DD` without a year, the next one from today.
+    MonthDay(u32, u32),
+}
+
+/// Parses a leading natural-language time such as `tomorrow 9am`, `next friday 17:00`,
+/// `at 2026-11-03 09:00 America/Los_Angeles` or `in 2 hours 30 minutes`.
+/// Returns the deadline and the remaining text.
+pub fn parse_natural_time(
+    input: &str,
+    now: DateTime<Utc>,
+    default_tz: Tz,
+) -> Option<(DateTime<Utc>, String)> {
+    let tokens = input.split_whitespace().collect::<Vec<&str>>();
+    if tokens.first()?.eq_ignore_ascii_case("in") {
+        let (delta, consumed) = parse_duration(&tokens[1..]).ok()?;
+        let deadline = now.checked_add_signed(delta)?;
+        return Some((deadline, tokens[1 + consumed..].join(" ")));
+    }
+
+    let (mut date, mut time, mut zone) = (None, None, None);
+    let mut i = 0;
+    while i < tokens.len() {
+        let preposition = ["at", "on"].contains(&tokens[i].to_lowercase().as_str());
+        let start = i + usize::from(preposition);
+        let Some((component, consumed)) = parse_component(&tokens, start) else {
+            break;
+        };
+        match component {
+            Component::Date(v) if date.is_none() => date = Some(v),
+            Component::Time(v) if time.is_none() => time = Some(v),
+            Component::Zone(v) if zone.is_none() => zone = Some(v),
+            _ => break,
+        }
+        i = start + consumed;
+    }
+    if date.is_none() && time.is_none() {
+        return None;
+    }
+
+    let tz = zone.unwrap_or(default_tz);
+    let local_now = now.with_timezone(&tz);
+    let today = local_now.date_naive();
+    let time = time.unwrap_or(NaiveTime::from_hms_opt(DEFAULT_HOUR, 0, 0)?);
+    let date = match date {
+        Some(DateSpec::Today) => today,
+        Some(DateSpec::Tomorrow) => today.checked_add_days(Days::new(1))?,
+        Some(DateSpec::Weekday(weekday)) => next_weekday(today, weekday)?,
+        Some(DateSpec::Absolute(date)) => date,
+        Some(DateSpec::MonthDay(month, day)) => (0..=MONTH_DAY_LOOKAHEAD)
+            .filter_map(|v| NaiveDate::from_ymd_opt(today.year() + v, month, day))
+            .find(|v| *v >= today)?,
+        // A bare time that already passed today means tomorrow.
+        None => match time > local_now.time() {
+            true => today,
+            false => today.checked_add_days(Days::new(1))?,
+        },
+    };
+    let deadline = resolve_local(tz, date.and_time(time))?;
+    Some((deadline, tokens[i..].join(" ")))
+}
+
+/// A leading time of day such as `09:45`, `at 5pm` or `noon`. Returns the time and the number of tokens used.
+pub fn parse_time_of_day(tokens: &[&str]) -> Option<(NaiveTime, usize)> {
+    let preposition = usize::from(tokens.first()?.eq_ignore_ascii_case("at"));
+    match parse_component(tokens, preposition)? {
+        (Component::Time(time), consumed) => Some((time, preposition + consumed)),
+        _ => None,
+    }
+}
+
+/// Converts a local wall clock time to UTC, skipping forward over DST gaps.
+pub fn resolve_local(tz: Tz, naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    tz.from_local_datetime(&naive)
+        .earliest()
+        .or_else(|| {
+            tz.from_local_datetime(&(naive + TimeDelta::hours(1)))
+                .earliest()
+        })
+        .map(|v| v.with_timezone(&Utc))
+}
+
+/// First occurrence of a weekday strictly after `today`.
+fn next_weekday(today: NaiveDate, weekday: Weekday) -> Option<NaiveDate> {
+    let days_ahead =
+        (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday() - 1) % 7 + 1;
+    today.checked_add_days(Days::new(u64::from(days_ahead)))
+}
+
+fn parse_component(tokens: &[&str], i: usize) -> Option<(Component, usize)> {
+    let token = tokens.get(i)?;
+    let lower = token.to_lowercase();
+    let next = tokens.get(i + 1).map(|v| v.to_lowercase());
+
+    match lower.as_str() {
+        "today" | "tonight" => return Some((Component::Date(DateSpec::Today), 1)),
+        "tomorrow" => return Some((Component::Date(DateSpec::Tomorrow), 1)),
+        "noon" => return Some((Component::Time(NaiveTime::from_hms_opt(12, 0, 0)?), 1)),
+        "midnight" => return Some((Component::Time(NaiveTime::MIN), 1)),
+        "next" | "this" => {
+            let weekday = next?.parse::<Weekday>().ok()?;
+            return Some((Component::Date(DateSpec::Weekday(weekday)), 2));
+        }
+        _ => (),
+    }
+    if let Ok(weekday) = lower.parse::<Weekday>() {
+        return Some((Component::Date(DateSpec::Weekday(weekday)), 1));
+    }
+    if let Some(date) = parse_date(&lower) {
+        return Some((Component::Date(date), 1));
+    }
+    if let Some(time) = parse_clock(&lower, next.as_deref()) {
+        return Some(time);
+    }
+    if token.contains('/') || lower == "utc" {
+        let tz = match lower.as_str() {
+            "utc" => Tz::UTC,
+            _ => token.parse::<Tz>().ok()?,
+        };
+        return Some((Component::Zone(tz), 1));
+    }
+    None
+}
+
+/// `YYYY-MM-DD` or `MM/DD[/YYYY]`.
+fn parse_date(token: &str) -> Option<DateSpec> {
+    for format in ["%Y-%m-%d", "%m/%d/%Y"] {
+        if let Ok(date) = NaiveDate::parse_from_str(token, format) {
+            return Some(DateSpec::Absolute(date));
+        }
+    }
+    let (month, day) = token.split_once('/')?;
+    let (month, day) = (month.parse().ok()?, day.parse().ok()?);
+    // Checked against a leap year so February 29 is accepted.
+    NaiveDate::from_ymd_opt(2000, month, day)?;
+    Some(DateSpec::MonthDay(month, day))
+}
+
+/// `17:00`, `9am`, `9:30pm` or `9 am`. Returns the time and the number of tokens used.
+fn parse_clock(token: &str, next: Option<&str>) -> Option<(Component, usize)> {
+    let (body, meridiem, consumed) = match token {
+        v if v.ends_with("am") || v.ends_with("pm") => {
+            (&v[..v.len() - 2], Some(&v[v.len() - 2..]), 1)
+        }
+        v => match next {
+            Some(next @ ("am" | "pm")) => (v, Some(next), 2),
+            _ => (v, None, 1),
+        },
+    };
+    let (hour, minute) = match body.split_once(':') {
+        Some((hour, minute)) => (hour.parse::<u32>().ok()?, minute.parse::<u32>().ok()?),
+        None if meridiem.is_some() => (body.parse::<u32>().ok()?, 0),
+        None => return None,
+    };
+    let hour = match meridiem {
+        Some(_) if !(1..=12).contains(&hour) => return None,
+        Some("am") => hour % 12,
+        Some(_) => hour % 12 + 12,
+        None => hour,
+    };
+    let time = NaiveTime::from_hms_opt(hour, minute, 0)?;
+    Some((Component::Time(time), consumed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Wednesday 2024-03-06 14:30 UTC, 09:30 in New York.
+    fn now() -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 3, 6, 14, 30, 0).unwrap()
+    }
+
+    fn parse(input: &str, tz: Tz) -> (DateTime<Utc>, String) {
+        parse_natural_time(input, now(), tz).unwrap()
+    }
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, month, day, hour, minute, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn tomorrow_morning() {
+        assert_eq!(
+            parse("tomorrow 9am stand-up", Tz::UTC),
+            (utc(3, 7, 9, 0), String::from("stand-up"))
+        );
+        assert_eq!(
+            parse("tomorrow at 9 am", Tz::America__New_York).0,
+            utc(3, 7, 14, 0)
+        );
+        // A date alone defaults to 9:00.
+        assert_eq!(parse("tomorrow", Tz::UTC).0, utc(3, 7, 9, 0));
+    }
+
+    #[test]
+    fn relative_durations() {
+        assert_eq!(
+            parse("in 2 hours check the oven", Tz::UTC),
+            (utc(3, 6, 16, 30), String::from("check the oven"))
+        );
+        assert_eq!(parse("in 1h30m", Tz::UTC).0, utc(3, 6, 16, 0));
+        assert_eq!(parse_natural_time("in a while", now(), Tz::UTC), None);
+    }
+
+    #[test]
+    fn weekday_names() {
+        assert_eq!(parse("friday 17:00", Tz::UTC).0, utc(3, 8, 17, 0));
+        assert_eq!(parse("next fri 5pm", Tz::UTC).0, utc(3, 8, 17, 0));
+        // The same weekday is a week ahead, never today.
+        assert_eq!(parse("wednesday noon", Tz::UTC).0, utc(3, 13, 12, 0));
+        assert_eq!(parse("on Monday", Tz::UTC).0, utc(3, 11, 9, 0));
+    }
+
+    #[test]
+    fn past_times_roll_over() {
+        // 8am already passed today, 3pm didn't.
+        assert_eq!(parse("8am", Tz::UTC).0, utc(3, 7, 8, 0));
+        assert_eq!(parse("at 3pm", Tz::UTC).0, utc(3, 6, 15, 0));
+        // Explicit dates are kept even when they passed.
+        assert_eq!(parse("today 8am", Tz::UTC).0, utc(3, 6, 8, 0));
+        assert_eq!(parse("2024-03-01 10:00", Tz::UTC).0, utc(3, 1, 10, 0));
+    }
+
+    #[test]
+    fn month_day_is_the_next_one() {
+        assert_eq!(parse("3/20 10:00", Tz::UTC).0, utc(3, 20, 10, 0));
+        // Today's date stays this year, even once its time passed.
+        assert_eq!(parse("3/6 8am", Tz::UTC).0, utc(3, 6, 8, 0));
+        assert_eq!(
+            parse("1/5 dentist", Tz::UTC),
+            (
+                Utc.with_ymd_and_hms(2025, 1, 5, 9, 0, 0).unwrap(),
+                String::from("dentist")
+            )
+        );
+        // 2024 is a leap year, the next February 29 is in 2028.
+        assert_eq!(
+            parse("2/29", Tz::UTC).0,
+            Utc.with_ymd_and_hms(2028, 2, 29, 9, 0, 0).unwrap()
+        );
+        // A year given is kept.
+        assert_eq!(parse("1/5/2024 9am", Tz::UTC).0, utc(1, 5, 9, 0));
+        assert_eq!(parse_natural_time("2/30", now(), Tz::UTC), None);
+        assert_eq!(parse_natural_time("13/1", now(), Tz::UTC), None);
+    }
+
+    #[test]
+    fn explicit_zone() {
+        assert_eq!(
+            parse("at 2024-11-03 09:00 America/Los_Angeles ship it", Tz::UTC),
+            (
+                Utc.with_ymd_and_hms(2024, 11, 3, 17, 0, 0).unwrap(),
+                String::from("ship it")
+            )
+        );
+        assert_eq!(parse("10:00 utc", Tz::Europe__Paris).0, utc(3, 7, 10, 0));
+    }
+
+    #[test]
+    fn dst_gap_skips_forward() {
+        // 2:30 doesn't exist in New York on 2024-03-10.
+        let naive = NaiveDate::from_ymd_opt(2024, 3, 10)
+            .unwrap()
+            .and_hms_opt(2, 30, 0)
+            .unwrap();
+        assert_eq!(
+            resolve_local(Tz::America__New_York, naive),
+            Some(utc(3, 10, 7, 30))
+        );
+    }
+
+    #[test]
+    fn not_a_time() {
+        assert_eq!(parse_natural_time("buy milk", now(), Tz::UTC), None);
+        assert_eq!(parse_natural_time("13pm", now(), Tz::UTC), None);
+        assert_eq!(parse_time_of_day(&["at", "9:45", "tea"]).unwrap().1, 2);
+    }
+}
@@ -7,6 +7,19 @@ use crate::err::{self, RKBServiceRequestErr};
 const TOKEN_FILE_PATH_STR: &str = "./Secrets.toml";
 const OPEN_WEATHER_TOKEN: &str = "OPEN_WEATHER_TOKEN";
 const DEEPSEEK_TOKEN: &str = "DEEPSEEK_TOKEN";
+const WEATHER_PROVIDER: &str = "WEATHER_PROVIDER";
+const OPEN_WEATHER_URL: &str = "OPEN_WEATHER_URL";
+const OPEN_METEO_URL: &str = "OPEN_METEO_URL";
+const OPEN_METEO_GEO_URL: &str = "OPEN_METEO_GEO_URL";
+const REVERSE_GEO_URL: &str = "REVERSE_GEO_URL";
+const LLM_PROVIDER: &str = "LLM_PROVIDER";
+const DEEPSEEK_URL: &str = "DEEPSEEK_URL";
+const OPENAI_TOKEN: &str = "OPENAI_TOKEN";
+const OPENAI_URL: &str = "OPENAI_URL";
+const OPENAI_MODEL: &str = "OPENAI_MODEL";
+const OPENAI_REASONING_MODEL: &str = "OPENAI_REASONING_MODEL";
+const OLLAMA_URL: &str = "OLLAMA_URL";
+const OLLAMA_MODEL: &str = "OLLAMA_MODEL";
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -23,6 +36,19 @@ pub struct Tokens {
 pub enum TokenType {
     OpenWeather,
     DeepSeek,
+    WeatherProvider,
+    OpenWeatherUrl,
+    OpenMeteoUrl,
+    OpenMeteoGeoUrl,
+    ReverseGeoUrl,
+    LlmProvider,
+    DeepSeekUrl,
+    OpenAi,
+    OpenAiUrl,
+    OpenAiModel,
+    OpenAiReasoningModel,
+    OllamaUrl,
+    OllamaModel,
 }
 
 impl TryFrom<String> for TokenType {
@@ -32,6 +58,19 @@ impl TryFrom<String> for TokenType {
         match value.as_str() {
             OPEN_WEATHER_TOKEN => Ok(TokenType::OpenWeather),
             DEEPSEEK_TOKEN => Ok(TokenType::DeepSeek),
+            WEATHER_PROVIDER => Ok(TokenType::WeatherProvider),
+            OPEN_WEATHER_URL => Ok(TokenType::OpenWeatherUrl),
+            OPEN_METEO_URL => Ok(TokenType::OpenMeteoUrl),
+            OPEN_METEO_GEO_URL => Ok(TokenType::OpenMeteoGeoUrl),
+            REVERSE_GEO_URL => Ok(TokenType::ReverseGeoUrl),
+            LLM_PROVIDER => Ok(TokenType::LlmProvider),
+            DEEPSEEK_URL => Ok(TokenType::DeepSeekUrl),
+            OPENAI_TOKEN => Ok(TokenType::OpenAi),
+            OPENAI_URL => Ok(TokenType::OpenAiUrl),
+            OPENAI_MODEL => Ok(TokenType::OpenAiModel),
+            OPENAI_REASONING_MODEL => Ok(TokenType::OpenAiReasoningModel),
+            OLLAMA_URL => Ok(TokenType::OllamaUrl),
+            OLLAMA_MODEL => Ok(TokenType::OllamaModel),
             _ => Err(format!("Failed to parse key ({}) into token.", value)),
         }
     }
@@ -63,6 +102,19 @@ impl Tokens {
             .get(key)
             .ok_or(err::RKBServiceRequestErr::Token(Error::MissingKey(*key)))
     }
+
+    pub fn get_or<'a>(&'a self, key: &TokenType, default: &'a str) -> &'a str {
+        self.tokens.get(key).map_or(default, |v| v.as_str())
+    }
+}
+
+/// Tokens given directly rather than read from the token file.
+impl FromIterator<(TokenType, String)> for Tokens {
+    fn from_iter<T: IntoIterator<Item = (TokenType, String)>>(iter: T) -> Self {
+        Self {
+            tokens: iter.into_iter().collect(),
+        }
+    }
 }
 
 impl Default for Tokens {
@@ -0,0 +1,116 @@
+use std::{fmt::Display, sync::Arc, time::Duration};
+
+use serenity::async_trait;
+
+use super::{CurrentWeather, Forecast, Location, WeatherProvider};
+use crate::{
+    cache::{CacheStats, TtlCache},
+    err::RKBServiceRequestErr,
+};
+
+const GEOCODE_TTL: Duration = Duration::from_secs(3 * 24 * 60 * 60);
+const CURRENT_TTL: Duration = Duration::from_secs(10 * 60);
+const FORECAST_TTL: Duration = Duration::from_secs(30 * 60);
+
+#[derive(Debug)]
+pub struct WeatherCache {
+    geocode: TtlCache<String, Location>,
+    current: TtlCache<String, CurrentWeather>,
+    forecast: TtlCache<String, Forecast>,
+}
+
+impl Default for WeatherCache {
+    fn default() -> Self {
+        WeatherCache {
+            geocode: TtlCache::new(GEOCODE_TTL),
+            current: TtlCache::new(CURRENT_TTL),
+            forecast: TtlCache::new(FORECAST_TTL),
+        }
+    }
+}
+
+impl Display for WeatherCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let row = |name: &str, stats: CacheStats| {
+            format!(
+                "{:<9}{:>8}{:>8}{:>8}",
+                name, stats.entries, stats.hits, stats.misses
+            )
+        };
+        write!(
+            f,
+            "```{:<9}{:>8}{:>8}{:>8}\n{}\n{}\n{}```",
+            "CACHE",
+            "ENTRIES",
+            "HITS",
+            "MISSES",
+            row("geocode", self.geocode.stats()),
+            row("current", self.current.stats()),
+            row("forecast", self.forecast.stats()),
+        )
+    }
+}
+
+/// Wraps a provider, serving repeat lookups from the shared `WeatherCache`.
+#[derive(Debug)]
+pub struct CachedWeatherProvider {
+    pub inner: Arc<dyn WeatherProvider>,
+    pub cache: Arc<WeatherCache>,
+    /// Skip cache reads, still refreshing the cache with the response.
+    pub fresh: bool,
+}
+
+fn location_key(location: &Location) -> String {
+    format!("{:.4},{:.4}", location.lat, location.lon)
+}
+
+#[async_trait]
+impl WeatherProvider for CachedWeatherProvider {
+    async fn geocode(&self, query: &str) -> Result<Location, RKBServiceRequestErr> {
+        let key = query.trim().to_lowercase();
+        if !self.fresh {
+            if let Some(location) = self.cache.geocode.get(&key) {
+                return Ok(location);
+            }
+        }
+        let location = self.inner.geocode(query).await?;
+        self.cache.geocode.insert(key, location.clone());
+        Ok(location)
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, RKBServiceRequestErr> {
+        let key = format!("@{:.4},{:.4}", lat, lon);
+        if !self.fresh {
+            if let Some(location) = self.cache.geocode.get(&key) {
+                return Ok(location);
+            }
+        }
+        let location = self.inner.reverse_geocode(lat, lon).await?;
+        self.cache.geocode.insert(key, location.clone());
+        Ok(location)
+    }
+
+    async fn current(&self, location: &Location) -> Result<CurrentWeather, RKBServiceRequestErr> {
+        let key = location_key(location);
+        if !self.fresh {
+            if let Some(current) = self.cache.current.get(&key) {
+                return Ok(current);
+            }
+        }
+        let current = self.inner.current(location).await?;
+        self.cache.current.insert(key, current.clone());
+        Ok(current)
+    }
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, RKBServiceRequestErr> {
+        let key = location_key(location);
+        if !self.fresh {
+            if let Some(forecast) = self.cache.forecast.get(&key) {
+                return Ok(forecast);
+            }
+        }
+        let forecast = self.inner.forecast(location).await?;
+        self.cache.forecast.insert(key, forecast.clone());
+        Ok(forecast)
+    }
+}
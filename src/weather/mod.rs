@@ -0,0 +1,126 @@
+pub mod cache;
+pub mod openmeteo;
+pub mod openweather;
+
+use std::sync::Arc;
+
+use chrono::NaiveDate;
+use serenity::async_trait;
+use thiserror::Error;
+
+use crate::{comfort::Units, err::RKBServiceRequestErr, token::TokenType, RKBServiceRequest};
+use cache::CachedWeatherProvider;
+use openmeteo::OpenMeteo;
+use openweather::OpenWeather;
+
+const OPEN_METEO: &str = "open-meteo";
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("failed to query weather provider")]
+    QueryError(&'static str),
+    #[error("failed to parse weather provider response")]
+    ParseError(&'static str),
+    #[error("location could not be found")]
+    UnknownLocation(String),
+}
+
+/// A geocoded place, independent of the provider that resolved it.
+#[derive(Default, Debug, Clone, PartialEq)]
+pub struct Location {
+    pub name: String,
+    pub zip: Option<String>,
+    pub country: String,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CurrentWeather {
+    pub name: String,
+    pub description: String,
+    pub temp: f64,
+    pub feels_like: f64,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    /// Humidity, %
+    pub humidity: f64,
+    pub wind_speed: f64,
+    /// Wind direction, degrees (meteorological)
+    pub wind_deg: f64,
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DailyForecast {
+    pub date: NaiveDate,
+    pub description: String,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    /// Probability of precipitation, %
+    pub precipitation: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Forecast {
+    pub name: String,
+    pub days: Vec<DailyForecast>,
+    pub units: Units,
+}
+
+#[async_trait]
+pub trait WeatherProvider: std::fmt::Debug + Send + Sync {
+    async fn geocode(&self, query: &str) -> Result<Location, RKBServiceRequestErr>;
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, RKBServiceRequestErr>;
+
+    async fn current(&self, location: &Location) -> Result<CurrentWeather, RKBServiceRequestErr>;
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, RKBServiceRequestErr>;
+}
+
+impl RKBServiceRequest {
+    /// Provider picked by `WEATHER_PROVIDER`, falling back to Open-Meteo when there is no OpenWeather key.
+    /// Lookups go through the shared cache unless `fresh` is set.
+    pub fn weather_provider(&self, fresh: bool) -> Arc<dyn WeatherProvider> {
+        let open_weather_key = self.tkn.get(&TokenType::OpenWeather).ok();
+        let inner: Arc<dyn WeatherProvider> = match (
+            self.tkn.get_or(&TokenType::WeatherProvider, ""),
+            open_weather_key,
+        ) {
+            (OPEN_METEO, _) | (_, None) => Arc::new(OpenMeteo::new(&self.tkn)),
+            (_, Some(api_key)) => Arc::new(OpenWeather::new(&self.tkn, api_key.to_string())),
+        };
+        Arc::new(CachedWeatherProvider {
+            inner,
+            cache: self.rsc.weather_cache.clone(),
+            fresh,
+        })
+    }
+}
+
+/// Description of a WMO weather interpretation code.
+pub fn wmo_description(code: u8) -> &'static str {
+    match code {
+        0 => "clear sky",
+        1 => "mainly clear",
+        2 => "partly cloudy",
+        3 => "overcast",
+        45 | 48 => "fog",
+        51 | 53 | 55 => "drizzle",
+        56 | 57 => "freezing drizzle",
+        61 => "light rain",
+        63 => "moderate rain",
+        65 => "heavy rain",
+        66 | 67 => "freezing rain",
+        71 => "light snow",
+        73 => "moderate snow",
+        75 => "heavy snow",
+        77 => "snow grains",
+        80..=82 => "rain showers",
+        85 | 86 => "snow showers",
+        95 => "thunderstorm",
+        96 | 99 => "thunderstorm with hail",
+        _ => "unknown conditions",
+    }
+}
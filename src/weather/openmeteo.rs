@@ -0,0 +1,426 @@
+use chrono::NaiveDate;
+use serde::Deserialize;
+use serenity::async_trait;
+
+use super::{
+    wmo_description, CurrentWeather, DailyForecast, Error, Forecast, Location, WeatherProvider,
+};
+use crate::{
+    comfort::Units,
+    err::RKBServiceRequestErr,
+    token::{TokenType, Tokens},
+};
+
+const PROVIDER: &str = "open-meteo";
+const DEFAULT_BASE_URL: &str = "https://api.open-meteo.com";
+const DEFAULT_GEO_URL: &str = "https://geocoding-api.open-meteo.com";
+/// Open-Meteo has no reverse geocoding, so coordinates are resolved through OpenStreetMap.
+const DEFAULT_REVERSE_GEO_URL: &str = "https://nominatim.openstreetmap.org";
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+const DEFAULT_COUNTRY_CODE: &str = "US";
+const FORECAST_DAYS: &str = "5";
+
+/// Keyless weather from open-meteo.com.
+#[derive(Debug, Clone)]
+pub struct OpenMeteo {
+    client: reqwest::Client,
+    base_url: String,
+    geo_url: String,
+    reverse_geo_url: String,
+    units: Units,
+}
+
+impl OpenMeteo {
+    pub fn new(tkn: &Tokens) -> Self {
+        OpenMeteo {
+            client: reqwest::Client::new(),
+            base_url: tkn
+                .get_or(&TokenType::OpenMeteoUrl, DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            geo_url: tkn
+                .get_or(&TokenType::OpenMeteoGeoUrl, DEFAULT_GEO_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            reverse_geo_url: tkn
+                .get_or(&TokenType::ReverseGeoUrl, DEFAULT_REVERSE_GEO_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            units: Units::Imperial,
+        }
+    }
+
+    fn unit_params(&self) -> [(&'static str, String); 2] {
+        let (temperature_unit, wind_speed_unit) = match self.units {
+            Units::Imperial => ("fahrenheit", "mph"),
+            Units::Standard | Units::Metric => ("celsius", "ms"),
+        };
+        [
+            ("temperature_unit", temperature_unit.to_string()),
+            ("wind_speed_unit", wind_speed_unit.to_string()),
+        ]
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        url: String,
+        query: &[(&str, String)],
+    ) -> Result<T, RKBServiceRequestErr> {
+        let response = self
+            .client
+            .get(url)
+            .header(reqwest::header::USER_AGENT, USER_AGENT)
+            .query(query)
+            .send()
+            .await
+            .and_then(|v| v.error_for_status())
+            .map_err(|_| Error::QueryError(PROVIDER))?
+            .json::<T>()
+            .await
+            .map_err(|_| Error::ParseError(PROVIDER))?;
+        Ok(response)
+    }
+
+    async fn forecast_json(
+        &self,
+        location: &Location,
+        fields: &[(&str, &str)],
+    ) -> Result<ForecastJson, RKBServiceRequestErr> {
+        let mut query = vec![
+            ("latitude", location.lat.to_string()),
+            ("longitude", location.lon.to_string()),
+            ("timezone", String::from("auto")),
+        ];
+        query.extend(self.unit_params());
+        query.extend(fields.iter().map(|(k, v)| (*k, v.to_string())));
+        self.get::<ForecastJson>(format!("{}/v1/forecast", self.base_url), &query)
+            .await
+    }
+
+    /// Kelvin is not offered, so standard units come back as metric.
+    fn reported_units(&self) -> Units {
+        match self.units {
+            Units::Standard => Units::Metric,
+            units => units,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GeocodingJson {
+    #[serde(default)]
+    pub results: Vec<GeocodingResult>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct GeocodingResult {
+    pub name: String,
+    pub latitude: f64,
+    pub longitude: f64,
+    #[serde(default)]
+    pub country_code: String,
+    #[serde(default)]
+    pub postcodes: Vec<String>,
+}
+
+impl From<GeocodingResult> for Location {
+    fn from(value: GeocodingResult) -> Self {
+        Location {
+            name: value.name,
+            zip: value.postcodes.into_iter().next(),
+            country: value.country_code,
+            lat: value.latitude,
+            lon: value.longitude,
+        }
+    }
+}
+
+/// Nominatim reverse geocoding result
+#[derive(Deserialize, Debug)]
+pub struct ReverseGeocodingJson {
+    pub lat: String,
+    pub lon: String,
+    #[serde(default)]
+    pub name: String,
+    #[serde(default)]
+    pub display_name: String,
+    #[serde(default)]
+    pub address: ReverseGeocodingAddress,
+}
+
+#[derive(Deserialize, Debug, Default)]
+pub struct ReverseGeocodingAddress {
+    pub city: Option<String>,
+    pub town: Option<String>,
+    pub village: Option<String>,
+    pub postcode: Option<String>,
+    #[serde(default)]
+    pub country_code: String,
+}
+
+impl From<ReverseGeocodingJson> for Location {
+    fn from(value: ReverseGeocodingJson) -> Self {
+        let address = value.address;
+        let name = address
+            .city
+            .or(address.town)
+            .or(address.village)
+            .or(Some(value.name).filter(|v| !v.is_empty()))
+            .unwrap_or(value.display_name);
+        Location {
+            name,
+            zip: address.postcode,
+            country: address.country_code.to_uppercase(),
+            lat: value.lat.parse().unwrap_or_default(),
+            lon: value.lon.parse().unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ForecastJson {
+    pub current: Option<CurrentJson>,
+    pub daily: Option<DailyJson>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct CurrentJson {
+    pub temperature_2m: f64,
+    pub relative_humidity_2m: f64,
+    pub apparent_temperature: f64,
+    pub weather_code: u8,
+    pub wind_speed_10m: f64,
+    pub wind_direction_10m: f64,
+}
+
+/// Daily aggregates, one entry per day in each vector.
+#[derive(Deserialize, Debug)]
+pub struct DailyJson {
+    pub time: Vec<String>,
+    pub weather_code: Vec<u8>,
+    pub temperature_2m_max: Vec<f64>,
+    pub temperature_2m_min: Vec<f64>,
+    #[serde(default)]
+    pub precipitation_probability_max: Vec<Option<f64>>,
+}
+
+impl DailyJson {
+    fn days(&self) -> Vec<DailyForecast> {
+        self.time
+            .iter()
+            .enumerate()
+            .filter_map(|(i, date)| {
+                Some(DailyForecast {
+                    date: NaiveDate::parse_from_str(date, "%Y-%m-%d").ok()?,
+                    description: wmo_description(*self.weather_code.get(i)?).to_string(),
+                    temp_min: *self.temperature_2m_min.get(i)?,
+                    temp_max: *self.temperature_2m_max.get(i)?,
+                    precipitation: self.precipitation_probability_max.get(i).copied().flatten(),
+                })
+            })
+            .collect()
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenMeteo {
+    async fn geocode(&self, query: &str) -> Result<Location, RKBServiceRequestErr> {
+        let mut params = vec![("name", query.to_string()), ("count", String::from("1"))];
+        if query.chars().all(|c| c.is_ascii_digit()) {
+            params.push(("countryCode", DEFAULT_COUNTRY_CODE.to_string()));
+        }
+        let location = self
+            .get::<GeocodingJson>(format!("{}/v1/search", self.geo_url), &params)
+            .await?
+            .results
+            .into_iter()
+            .next()
+            .ok_or(Error::UnknownLocation(query.to_string()))?;
+        Ok(location.into())
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, RKBServiceRequestErr> {
+        let location = self
+            .get::<ReverseGeocodingJson>(
+                format!("{}/reverse", self.reverse_geo_url),
+                &[
+                    ("lat", lat.to_string()),
+                    ("lon", lon.to_string()),
+                    ("format", String::from("jsonv2")),
+                ],
+            )
+            .await
+            .map_err(|_| Error::UnknownLocation(format!("{}, {}", lat, lon)))?;
+        Ok(location.into())
+    }
+
+    async fn current(&self, location: &Location) -> Result<CurrentWeather, RKBServiceRequestErr> {
+        let response = self
+            .forecast_json(
+                location,
+                &[
+                    ("current", "temperature_2m,relative_humidity_2m,apparent_temperature,weather_code,wind_speed_10m,wind_direction_10m"),
+                    ("daily", "temperature_2m_max,temperature_2m_min,weather_code"),
+                    ("forecast_days", "1"),
+                ],
+            )
+            .await?;
+        let current = response.current.ok_or(Error::ParseError(PROVIDER))?;
+        let today = response
+            .daily
+            .map(|v| v.days())
+            .and_then(|v| v.into_iter().next());
+        Ok(CurrentWeather {
+            name: location.name.clone(),
+            description: wmo_description(current.weather_code).to_string(),
+            temp: current.temperature_2m,
+            feels_like: current.apparent_temperature,
+            temp_min: today
+                .as_ref()
+                .map_or(current.temperature_2m, |v| v.temp_min),
+            temp_max: today.map_or(current.temperature_2m, |v| v.temp_max),
+            humidity: current.relative_humidity_2m,
+            wind_speed: current.wind_speed_10m,
+            wind_deg: current.wind_direction_10m,
+            units: self.reported_units(),
+        })
+    }
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, RKBServiceRequestErr> {
+        let response = self
+            .forecast_json(
+                location,
+                &[
+                    ("daily", "weather_code,temperature_2m_max,temperature_2m_min,precipitation_probability_max"),
+                    ("forecast_days", FORECAST_DAYS),
+                ],
+            )
+            .await?;
+        let daily = response.daily.ok_or(Error::ParseError(PROVIDER))?;
+        Ok(Forecast {
+            name: location.name.clone(),
+            days: daily.days(),
+            units: self.reported_units(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::stub::{Route, Stub};
+
+    fn provider(stub: &Stub) -> OpenMeteo {
+        let tkn = Tokens::from_iter([
+            (TokenType::OpenMeteoUrl, stub.url.clone()),
+            (TokenType::OpenMeteoGeoUrl, stub.url.clone()),
+            (TokenType::ReverseGeoUrl, stub.url.clone()),
+        ]);
+        OpenMeteo::new(&tkn)
+    }
+
+    fn location() -> Location {
+        Location {
+            name: String::from("Berlin"),
+            lat: 52.52,
+            lon: 13.41,
+            ..Location::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_current_weather() {
+        let stub = Stub::serve(vec![Route::json(
+            "/v1/forecast",
+            json!({
+                "current": {
+                    "temperature_2m": 48.2, "relative_humidity_2m": 87,
+                    "apparent_temperature": 44.6, "weather_code": 61,
+                    "wind_speed_10m": 7.5, "wind_direction_10m": 210,
+                },
+                "daily": {
+                    "time": ["2024-10-19"],
+                    "weather_code": [61],
+                    "temperature_2m_max": [52.1],
+                    "temperature_2m_min": [43.9],
+                },
+            }),
+        )])
+        .await;
+        let weather = provider(&stub).current(&location()).await.unwrap();
+        assert_eq!(weather.name, "Berlin");
+        assert_eq!(weather.description, wmo_description(61));
+        assert_eq!((weather.temp, weather.feels_like), (48.2, 44.6));
+        assert_eq!((weather.temp_min, weather.temp_max), (43.9, 52.1));
+        assert_eq!(weather.humidity, 87.0);
+        assert_eq!(weather.units, Units::Imperial);
+        let (request_line, _) = stub.requests.lock().unwrap()[0].clone();
+        assert!(
+            request_line.contains("temperature_unit=fahrenheit"),
+            "{}",
+            request_line
+        );
+    }
+
+    #[tokio::test]
+    async fn parses_forecast() {
+        let stub = Stub::serve(vec![Route::json(
+            "/v1/forecast",
+            json!({
+                "daily": {
+                    "time": ["2024-10-19", "2024-10-20"],
+                    "weather_code": [0, 3],
+                    "temperature_2m_max": [60.0, 58.5],
+                    "temperature_2m_min": [45.0, 44.1],
+                    "precipitation_probability_max": [10, null],
+                },
+            }),
+        )])
+        .await;
+        let forecast = provider(&stub).forecast(&location()).await.unwrap();
+        assert_eq!(forecast.days.len(), 2);
+        assert_eq!(
+            forecast.days[0].date,
+            NaiveDate::from_ymd_opt(2024, 10, 19).unwrap()
+        );
+        assert_eq!(forecast.days[0].description, wmo_description(0));
+        assert_eq!(forecast.days[0].precipitation, Some(10.0));
+        assert_eq!(
+            (forecast.days[1].temp_min, forecast.days[1].temp_max),
+            (44.1, 58.5)
+        );
+        assert_eq!(forecast.days[1].precipitation, None);
+    }
+
+    #[tokio::test]
+    async fn geocodes_through_search_and_reverse() {
+        let stub = Stub::serve(vec![
+            Route::json(
+                "/v1/search",
+                json!({"results": [{
+                    "name": "Berlin", "latitude": 52.52437, "longitude": 13.41053,
+                    "country_code": "DE", "postcodes": ["10115"],
+                }]}),
+            ),
+            Route::json(
+                "/reverse",
+                json!({
+                    "lat": "52.5200", "lon": "13.4050", "display_name": "Mitte, Berlin",
+                    "address": {"city": "Berlin", "postcode": "10178", "country_code": "de"},
+                }),
+            ),
+        ])
+        .await;
+        let provider = provider(&stub);
+        let location = provider.geocode("Berlin").await.unwrap();
+        assert_eq!(location.name, "Berlin");
+        assert_eq!(location.zip.as_deref(), Some("10115"));
+        assert_eq!((location.lat, location.lon), (52.52437, 13.41053));
+        let location = provider.reverse_geocode(52.52, 13.405).await.unwrap();
+        assert_eq!(location.name, "Berlin");
+        assert_eq!(location.country, "DE");
+        assert_eq!(location.lat, 52.52);
+    }
+}
@@ -0,0 +1,537 @@
+use std::collections::BTreeMap;
+
+use chrono::{DateTime, FixedOffset, NaiveDate};
+use serde::{Deserialize, Serialize};
+use serenity::async_trait;
+
+use super::{CurrentWeather, DailyForecast, Error, Forecast, Location, WeatherProvider};
+use crate::{
+    comfort::Units,
+    err::RKBServiceRequestErr,
+    token::{TokenType, Tokens},
+};
+
+const PROVIDER: &str = "openweather";
+const DEFAULT_BASE_URL: &str = "https://api.openweathermap.org";
+const DEFAULT_COUNTRY_CODE: &str = "US";
+
+#[derive(Debug, Clone)]
+pub struct OpenWeather {
+    client: reqwest::Client,
+    base_url: String,
+    api_key: String,
+    units: Units,
+}
+
+impl OpenWeather {
+    pub fn new(tkn: &Tokens, api_key: String) -> Self {
+        OpenWeather {
+            client: reqwest::Client::new(),
+            base_url: tkn
+                .get_or(&TokenType::OpenWeatherUrl, DEFAULT_BASE_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            api_key,
+            units: Units::Imperial,
+        }
+    }
+
+    fn units_param(&self) -> &'static str {
+        match self.units {
+            Units::Standard => "standard",
+            Units::Metric => "metric",
+            Units::Imperial => "imperial",
+        }
+    }
+
+    pub(crate) async fn get<T: serde::de::DeserializeOwned>(
+        &self,
+        path: &str,
+        query: &[(&str, String)],
+    ) -> Result<T, RKBServiceRequestErr> {
+        let response = self
+            .client
+            .get(format!("{}{}", self.base_url, path))
+            .query(query)
+            .query(&[("appid", &self.api_key)])
+            .send()
+            .await
+            .and_then(|v| v.error_for_status())
+            .map_err(|_| Error::QueryError(PROVIDER))?
+            .json::<T>()
+            .await
+            .map_err(|_| Error::ParseError(PROVIDER))?;
+        Ok(response)
+    }
+}
+
+#[derive(Default, Debug, Clone, Serialize, Deserialize)]
+pub struct GeoJson {
+    #[serde(default)]
+    pub zip: String,
+    pub name: String,
+    pub lat: f64,
+    pub lon: f64,
+    pub country: String,
+}
+
+impl From<GeoJson> for Location {
+    fn from(value: GeoJson) -> Self {
+        Location {
+            name: value.name,
+            zip: Some(value.zip).filter(|v| !v.is_empty()),
+            country: value.country,
+            lat: value.lat,
+            lon: value.lon,
+        }
+    }
+}
+
+/// From openweathermap-0.2.4
+/// Location coordinates
+
+#[derive(Deserialize, Debug)]
+
+pub struct Coord {
+    /// geo location, longitude
+    pub lon: f64,
+
+    /// geo location, latitude
+    pub lat: f64,
+}
+
+/// Weather condition description
+
+#[derive(Deserialize, Debug)]
+
+pub struct Weather {
+    /// Weather condition id
+    pub id: u64,
+
+    /// Group of weather parameters (Rain, Snow, Extreme etc.)
+    pub main: String,
+
+    /// Weather condition
+    pub description: String,
+
+    /// Weather icon id
+    pub icon: String,
+}
+
+/// Detailed weather report
+
+#[derive(Deserialize, Debug)]
+
+pub struct Main {
+    /// Temperature. Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
+    pub temp: f64,
+
+    /// Temperature. This temperature parameter accounts for the human perception of weather.
+    /// Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
+    pub feels_like: f64,
+
+    /// Atmospheric pressure (on the sea level, if there is no sea_level or grnd_level data), hPa
+    pub pressure: f64,
+
+    /// Humidity, %
+    pub humidity: f64,
+
+    /// Minimum temperature at the moment.
+    /// This is minimal currently observed temperature (within large megalopolises and urban areas).
+    /// Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
+    pub temp_min: f64,
+
+    /// Maximum temperature at the moment.
+    /// This is maximal currently observed temperature (within large megalopolises and urban areas).
+    /// Unit Default: Kelvin, Metric: Celsius, Imperial: Fahrenheit.
+    pub temp_max: f64,
+
+    /// Atmospheric pressure on the sea level, hPa
+    pub sea_level: Option<f64>,
+
+    /// Atmospheric pressure on the ground level, hPa
+    pub grnd_level: Option<f64>,
+}
+
+/// Detailed wind report
+
+#[derive(Deserialize, Debug)]
+
+pub struct Wind {
+    /// Wind speed. Unit Default: meter/sec, Metric: meter/sec, Imperial: miles/hour.
+    pub speed: f64,
+
+    /// Wind direction, degrees (meteorological)
+    pub deg: f64,
+
+    /// Wind gust. Unit Default: meter/sec, Metric: meter/sec, Imperial: miles/hour
+    pub gust: Option<f64>,
+}
+
+/// Detailed clouds report
+
+#[derive(Deserialize, Debug)]
+
+pub struct Clouds {
+    /// Cloudiness, %
+    pub all: f64,
+}
+
+/// Rain or snow volume report
+
+#[derive(Deserialize, Debug)]
+
+pub struct Volume {
+    /// Volume for the last 1 hour, mm
+    #[serde(rename = "1h")]
+    pub h1: Option<f64>,
+
+    /// Volume for the last 3 hours, mm
+    #[serde(rename = "3h")]
+    pub h3: Option<f64>,
+}
+
+/// Additional information
+
+#[derive(Deserialize, Debug)]
+
+pub struct Sys {
+    /// Internal parameter
+    #[serde(rename = "type")]
+    pub type_: Option<u64>,
+
+    /// Internal parameter
+    pub id: Option<u64>,
+
+    /// Internal parameter
+    pub message: Option<f64>,
+
+    /// Country code (GB, JP etc.)
+    pub country: String,
+
+    /// Sunrise time, unix, UTC
+    pub sunrise: i64,
+
+    /// Sunset time, unix, UTC
+    pub sunset: i64,
+}
+
+#[derive(Deserialize, Debug)]
+
+/// current weather report in a nested struct
+pub struct WeatherJson {
+    /// report origin coordinates
+    pub coord: Coord,
+
+    /// vector with one item of weather condition descriptions
+    pub weather: Vec<Weather>,
+
+    /// Internal parameter
+    pub base: String,
+
+    /// detailed weather report
+    pub main: Main,
+
+    /// Visibility, meter
+    pub visibility: u64,
+
+    /// detailed wind report
+    pub wind: Wind,
+
+    /// detailed clouds report
+    pub clouds: Clouds,
+
+    /// detailed rain report
+    pub rain: Option<Volume>,
+
+    /// detailed snow report
+    pub snow: Option<Volume>,
+
+    /// Time of data calculation, unix, UTC
+    pub dt: i64,
+
+    /// additional information
+    pub sys: Sys,
+
+    /// Shift in seconds from UTC
+    pub timezone: i64,
+
+    /// City ID
+    pub id: u64,
+
+    /// City name
+    pub name: String,
+
+    /// Internal parameter
+    pub cod: u64,
+}
+
+/// 5 day forecast in 3 hour steps
+#[derive(Deserialize, Debug)]
+pub struct ForecastJson {
+    /// forecast steps
+    pub list: Vec<ForecastStep>,
+
+    /// forecast location
+    pub city: ForecastCity,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ForecastStep {
+    /// Time of forecasted data, unix, UTC
+    pub dt: i64,
+
+    /// detailed weather report
+    pub main: Main,
+
+    /// vector with one item of weather condition descriptions
+    pub weather: Vec<Weather>,
+
+    /// Probability of precipitation, 0 to 1
+    pub pop: Option<f64>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ForecastCity {
+    /// City name
+    pub name: String,
+
+    /// Shift in seconds from UTC
+    pub timezone: i32,
+}
+
+#[async_trait]
+impl WeatherProvider for OpenWeather {
+    async fn geocode(&self, query: &str) -> Result<Location, RKBServiceRequestErr> {
+        if query.chars().all(|c| c.is_ascii_digit()) {
+            let geojson = self
+                .get::<GeoJson>(
+                    "/geo/1.0/zip",
+                    &[("zip", format!("{},{}", query, DEFAULT_COUNTRY_CODE))],
+                )
+                .await?;
+            return Ok(geojson.into());
+        }
+        let geojson = self
+            .get::<Vec<GeoJson>>(
+                "/geo/1.0/direct",
+                &[("q", query.to_string()), ("limit", String::from("1"))],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(Error::UnknownLocation(query.to_string()))?;
+        Ok(geojson.into())
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, RKBServiceRequestErr> {
+        let geojson = self
+            .get::<Vec<GeoJson>>(
+                "/geo/1.0/reverse",
+                &[
+                    ("lat", lat.to_string()),
+                    ("lon", lon.to_string()),
+                    ("limit", String::from("1")),
+                ],
+            )
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(Error::UnknownLocation(format!("{}, {}", lat, lon)))?;
+        Ok(geojson.into())
+    }
+
+    async fn current(&self, location: &Location) -> Result<CurrentWeather, RKBServiceRequestErr> {
+        let response = self
+            .get::<WeatherJson>(
+                "/data/2.5/weather",
+                &[
+                    ("lat", location.lat.to_string()),
+                    ("lon", location.lon.to_string()),
+                    ("units", self.units_param().to_string()),
+                ],
+            )
+            .await?;
+        let description = response
+            .weather
+            .first()
+            .map(|v| v.description.clone())
+            .unwrap_or_default();
+        Ok(CurrentWeather {
+            name: response.name,
+            description,
+            temp: response.main.temp,
+            feels_like: response.main.feels_like,
+            temp_min: response.main.temp_min,
+            temp_max: response.main.temp_max,
+            humidity: response.main.humidity,
+            wind_speed: response.wind.speed,
+            wind_deg: response.wind.deg,
+            units: self.units,
+        })
+    }
+
+    async fn forecast(&self, location: &Location) -> Result<Forecast, RKBServiceRequestErr> {
+        let response = self
+            .get::<ForecastJson>(
+                "/data/2.5/forecast",
+                &[
+                    ("lat", location.lat.to_string()),
+                    ("lon", location.lon.to_string()),
+                    ("units", self.units_param().to_string()),
+                ],
+            )
+            .await?;
+        let offset =
+            FixedOffset::east_opt(response.city.timezone).ok_or(Error::ParseError(PROVIDER))?;
+        let mut steps_by_date: BTreeMap<NaiveDate, Vec<ForecastStep>> = BTreeMap::new();
+        for step in response.list {
+            let Some(datetime) = DateTime::from_timestamp(step.dt, 0) else {
+                continue;
+            };
+            let date = datetime.with_timezone(&offset).date_naive();
+            steps_by_date.entry(date).or_default().push(step);
+        }
+        let days = steps_by_date
+            .into_iter()
+            .map(|(date, steps)| DailyForecast {
+                date,
+                description: steps
+                    .get(steps.len() / 2)
+                    .and_then(|v| v.weather.first())
+                    .map(|v| v.description.clone())
+                    .unwrap_or_default(),
+                temp_min: steps
+                    .iter()
+                    .map(|v| v.main.temp_min)
+                    .fold(f64::INFINITY, f64::min),
+                temp_max: steps
+                    .iter()
+                    .map(|v| v.main.temp_max)
+                    .fold(f64::NEG_INFINITY, f64::max),
+                precipitation: steps
+                    .iter()
+                    .filter_map(|v| v.pop)
+                    .reduce(f64::max)
+                    .map(|v| v * 100.0),
+            })
+            .collect();
+        Ok(Forecast {
+            name: response.city.name,
+            days,
+            units: self.units,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::stub::{Route, Stub};
+
+    fn main_json(temp: f64, temp_min: f64, temp_max: f64) -> serde_json::Value {
+        json!({
+            "temp": temp, "feels_like": temp - 2.0, "pressure": 1012, "humidity": 81,
+            "temp_min": temp_min, "temp_max": temp_max,
+        })
+    }
+
+    fn provider(stub: &Stub) -> OpenWeather {
+        let tkn = Tokens::from_iter([(TokenType::OpenWeatherUrl, format!("{}/", stub.url))]);
+        OpenWeather::new(&tkn, String::from("key"))
+    }
+
+    fn location() -> Location {
+        Location {
+            name: String::from("London"),
+            lat: 51.5085,
+            lon: -0.1257,
+            ..Location::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn parses_current_weather() {
+        let stub = Stub::serve(vec![Route::json(
+            "/data/2.5/weather",
+            json!({
+                "coord": {"lon": -0.1257, "lat": 51.5085},
+                "weather": [{"id": 500, "main": "Rain", "description": "light rain", "icon": "10d"}],
+                "base": "stations",
+                "main": main_json(55.4, 53.6, 57.2),
+                "visibility": 10000,
+                "wind": {"speed": 9.2, "deg": 240},
+                "clouds": {"all": 75},
+                "rain": {"1h": 0.3},
+                "dt": 1729353600,
+                "sys": {"country": "GB", "sunrise": 1729320000, "sunset": 1729357200},
+                "timezone": 3600,
+                "id": 2643743,
+                "name": "London",
+                "cod": 200,
+            }),
+        )])
+        .await;
+        let weather = provider(&stub).current(&location()).await.unwrap();
+        assert_eq!(weather.name, "London");
+        assert_eq!(weather.description, "light rain");
+        assert_eq!(weather.temp, 55.4);
+        assert_eq!(weather.feels_like, 53.4);
+        assert_eq!((weather.temp_min, weather.temp_max), (53.6, 57.2));
+        assert_eq!((weather.wind_speed, weather.wind_deg), (9.2, 240.0));
+        assert_eq!(weather.units, Units::Imperial);
+        let (request_line, _) = stub.requests.lock().unwrap()[0].clone();
+        assert!(request_line.contains("units=imperial"), "{}", request_line);
+        assert!(request_line.contains("appid=key"), "{}", request_line);
+    }
+
+    #[tokio::test]
+    async fn groups_forecast_steps_by_local_day() {
+        let step = |dt: i64, temp_min: f64, temp_max: f64, pop: f64| {
+            json!({
+                "dt": dt,
+                "main": main_json(temp_max, temp_min, temp_max),
+                "weather": [{"id": 800, "main": "Clear", "description": "clear sky", "icon": "01d"}],
+                "pop": pop,
+            })
+        };
+        // 2024-10-19 22:00 UTC is already the 20th at UTC+3.
+        let stub = Stub::serve(vec![Route::json(
+            "/data/2.5/forecast",
+            json!({
+                "list": [
+                    step(1729328400, 50.0, 54.0, 0.1),
+                    step(1729339200, 52.0, 60.0, 0.4),
+                    step(1729375200, 45.0, 48.0, 0.0),
+                ],
+                "city": {"name": "Istanbul", "timezone": 10800},
+            }),
+        )])
+        .await;
+        let forecast = provider(&stub).forecast(&location()).await.unwrap();
+        assert_eq!(forecast.name, "Istanbul");
+        assert_eq!(forecast.days.len(), 2);
+        let first = &forecast.days[0];
+        assert_eq!(first.date, NaiveDate::from_ymd_opt(2024, 10, 19).unwrap());
+        assert_eq!((first.temp_min, first.temp_max), (50.0, 60.0));
+        assert_eq!(first.precipitation, Some(40.0));
+        assert_eq!(
+            forecast.days[1].date,
+            NaiveDate::from_ymd_opt(2024, 10, 20).unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn reports_server_errors() {
+        let stub = Stub::serve(Vec::new()).await;
+        let result = provider(&stub).current(&location()).await;
+        assert!(matches!(
+            result,
+            Err(RKBServiceRequestErr::WeatherProvider(Error::QueryError(
+                PROVIDER
+            )))
+        ));
+    }
+}